@@ -0,0 +1,30 @@
+struct VertexIn {
+    @location(0) pos: vec2<f32>,
+    @location(1) cell: vec2<i32>,
+}
+
+struct VertexOut {
+    @builtin(position) clip_pos: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+@group(0)@binding(0)
+var<uniform> proj: mat4x4<f32>;
+
+@vertex
+fn vs_main(in: VertexIn) -> VertexOut {
+    // Row y is drawn below row y - 1, like the dense grid.
+    let corner = vec2<f32>(f32(in.cell.x), -f32(in.cell.y) - 1.0);
+
+    var out: VertexOut;
+    out.clip_pos = proj * vec4<f32>(corner + in.pos, 0.0, 1.0);
+    // The dense grid's gradient, repeated every chunk.
+    let c = vec2<f32>((in.cell % 64 + 64) % 64) / 64.0;
+    out.color = vec3<f32>(c, 1.0 - c.x);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
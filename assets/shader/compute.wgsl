@@ -4,53 +4,449 @@ var<uniform> grid_size: vec2<f32>;
 var<storage> cell_in: array<u32>;
 @group(0)@binding(2)
 var<storage, read_write> cell_out: array<u32>;
+// Laid out by `Rule::uniform`.
+struct Rule {
+    // Bitmasks indexed by live neighbour count.
+    birth: u32,
+    survival: u32,
+    // More than 2 for Generations rules where cells decay through states 2.. back to 0.
+    states: u32,
+    // Larger than Life neighbourhood radius and shape: 0 Moore, 1 von Neumann, 2 circular.
+    range: u32,
+    shape: u32,
+    // 1 if a live cell counts itself.
+    middle: u32,
+    // Inclusive Larger than Life count ranges, used instead of the bitmasks.
+    birth_range: vec2<u32>,
+    survival_range: vec2<u32>,
+    // Where the root of a `.rule` file's tree starts in `tree`, and its 4 or 8 neighbours.
+    tree_root: u32,
+    tree_neighbours: u32,
+    // `Tiling::uniform`: 0 squares, 1 hexagons, 2 triangles with 12 neighbours, 3 with 3.
+    tiling: u32,
+}
+
+@group(0)@binding(3)
+var<uniform> rule: Rule;
+// How the left/right (x) and top/bottom (y) edges are joined: 0 dead, 1 wrapped, 2 wrapped
+// with the other axis mirrored. Must match `Topology::neighbour`.
+@group(0)@binding(4)
+var<uniform> topology: vec2<u32>;
+// `Rule::table`: 1 where the 3x3 block, bit i the cell i in reading order, has a live middle
+// cell next generation.
+@group(0)@binding(5)
+var<storage> table: array<u32, 512>;
+// `RuleTree::nodes`: one child per state, the new state on the last level and where the child
+// node starts on the others.
+@group(0)@binding(6)
+var<storage> tree: array<u32>;
+// Cell edits for `cp_edit`: their count, then a word index, the bits to replace and their new
+// value for each.
+@group(0)@binding(7)
+var<storage> edits: array<u32>;
 
 fn cell_index(cell: vec2<u32>) -> u32 {
-    return (cell.y % u32(grid_size.y)) * u32(grid_size.x) + cell.x % u32(grid_size.x);
+    return cell.y * u32(grid_size.x) + cell.x;
+}
+
+// `v` wrapped into `0..n`, without `%` on negative values.
+fn wrap(v: i32, n: i32) -> i32 {
+    if v < 0 {
+        return n - 1 - (-v - 1) % n;
+    }
+    return v % n;
+}
+
+// Where `cell` ends up once the edges are crossed, x is -1 if it fell off a dead edge.
+fn resolve(cell: vec2<i32>) -> vec2<i32> {
+    let size = vec2<i32>(grid_size);
+    var p = cell;
+    if p.x < 0 || p.x >= size.x {
+        if topology.x == 0u {
+            return vec2<i32>(-1);
+        }
+        p.x = wrap(p.x, size.x);
+        if topology.x == 2u {
+            p.y = size.y - 1 - p.y;
+        }
+    }
+    if p.y < 0 || p.y >= size.y {
+        if topology.y == 0u {
+            return vec2<i32>(-1);
+        }
+        p.y = wrap(p.y, size.y);
+        if topology.y == 2u {
+            p.x = size.x - 1 - p.x;
+        }
+    }
+    return p;
 }
 
-fn cell_active(x: u32, y: u32) -> u32 {
-    return cell_in[cell_index(vec2<u32>(x, y))];
+fn cell_state(cell: vec2<i32>) -> u32 {
+    let p = resolve(cell);
+    if p.x < 0 {
+        return 0u;
+    }
+    return cell_in[cell_index(vec2<u32>(p))];
+}
+
+fn cell_active(cell: vec2<i32>) -> u32 {
+    // Dying cells are not live neighbours.
+    return u32(cell_state(cell) == 1u);
 }
 
 @compute
 @workgroup_size(8, 8)
 fn cp_main(@builtin(global_invocation_id) cell: vec3<u32>) {
-    let max_x = u32(grid_size.x) - 1u;
-    let max_y = u32(grid_size.y) - 1u;
+    if cell.x >= u32(grid_size.x) || cell.y >= u32(grid_size.y) {
+        return;
+    }
 
-    var cell_x_left = max_x;
-    if cell.x > 0u {
-        cell_x_left = cell.x - 1u;
-    };
+    let c = vec2<i32>(cell.xy);
+    var active_neighbours = 0u;
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            if dx != 0 || dy != 0 {
+                active_neighbours += cell_active(c + vec2<i32>(dx, dy));
+            }
+        }
+    }
 
-    var cell_x_right = cell.x + 1u;
-    if cell.x == max_x {
-        cell_x_right = 0u;
-    };
+    let idx = cell_index(cell.xy);
+    let state = cell_in[idx];
+    if state == 0u {
+        cell_out[idx] = (rule.birth >> active_neighbours) & 1u;
+    } else if state == 1u && ((rule.survival >> active_neighbours) & 1u) == 1u {
+        cell_out[idx] = 1u;
+    } else {
+        cell_out[idx] = (state + 1u) % rule.states;
+    }
+}
 
-    var cell_y_left = max_y;
-    if cell.y > 0u {
-        cell_y_left = cell.y - 1u;
-    };
+// Isotropic non-totalistic rules, which depend on where the live neighbours are and not just
+// how many there are.
+@compute
+@workgroup_size(8, 8)
+fn cp_isotropic(@builtin(global_invocation_id) cell: vec3<u32>) {
+    if cell.x >= u32(grid_size.x) || cell.y >= u32(grid_size.y) {
+        return;
+    }
 
-    var cell_y_right = cell.y + 1u;
-    if cell.y == max_y {
-        cell_y_right = 0u;
-    };
+    let c = vec2<i32>(cell.xy);
+    var block = 0u;
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            block |= cell_active(c + vec2<i32>(dx, dy)) << u32((dy + 1) * 3 + dx + 1);
+        }
+    }
 
-    let active_neighbours = cell_active(cell_x_right, cell_y_right) + cell_active(cell_x_right, cell.y) + cell_active(cell_x_right, cell_y_left) + cell_active(cell.x, cell_y_right) + cell_active(cell.x, cell.y) + cell_active(cell.x, cell_y_left) + cell_active(cell_x_left, cell_y_right) + cell_active(cell_x_left, cell.y) + cell_active(cell_x_left, cell_y_left);
+    let idx = cell_index(cell.xy);
+    let state = cell_in[idx];
+    let live = table[block];
+    if state == 0u {
+        cell_out[idx] = live;
+    } else if state == 1u && live == 1u {
+        cell_out[idx] = 1u;
+    } else {
+        cell_out[idx] = (state + 1u) % rule.states;
+    }
+}
+
+// Life-like and Generations rules on hexagons or triangles, their neighbours are those of
+// `Tiling::neighbours`.
+@compute
+@workgroup_size(8, 8)
+fn cp_tiled(@builtin(global_invocation_id) cell: vec3<u32>) {
+    if cell.x >= u32(grid_size.x) || cell.y >= u32(grid_size.y) {
+        return;
+    }
+
+    let c = vec2<i32>(cell.xy);
+    var active_neighbours = 0u;
+    if rule.tiling == 1u {
+        // The Moore neighbours but the top right and bottom left ones, which like the middle
+        // cell have `dx + dy == 0`.
+        for (var dy = -1; dy <= 1; dy++) {
+            for (var dx = -1; dx <= 1; dx++) {
+                if dx + dy != 0 {
+                    active_neighbours += cell_active(c + vec2<i32>(dx, dy));
+                }
+            }
+        }
+    } else {
+        // Across the base, down for a triangle pointing up, and the two sides.
+        var base = 1;
+        if (cell.x + cell.y) % 2u == 1u {
+            base = -1;
+        }
+        active_neighbours += cell_active(c + vec2<i32>(0, base));
+        active_neighbours += cell_active(c + vec2<i32>(-1, 0));
+        active_neighbours += cell_active(c + vec2<i32>(1, 0));
+        if rule.tiling == 2u {
+            // And those touching a corner.
+            active_neighbours += cell_active(c + vec2<i32>(-2, 0));
+            active_neighbours += cell_active(c + vec2<i32>(2, 0));
+            for (var dx = -2; dx <= 2; dx++) {
+                if dx != 0 {
+                    active_neighbours += cell_active(c + vec2<i32>(dx, base));
+                }
+            }
+            for (var dx = -1; dx <= 1; dx++) {
+                active_neighbours += cell_active(c + vec2<i32>(dx, -base));
+            }
+        }
+    }
 
     let idx = cell_index(cell.xy);
-    switch active_neighbours {
-        case 2u: {
-            cell_out[idx] = cell_in[idx];
+    let state = cell_in[idx];
+    if state == 0u {
+        cell_out[idx] = (rule.birth >> active_neighbours) & 1u;
+    } else if state == 1u && ((rule.survival >> active_neighbours) & 1u) == 1u {
+        cell_out[idx] = 1u;
+    } else {
+        cell_out[idx] = (state + 1u) % rule.states;
+    }
+}
+
+// Rules from `.rule` files, walking the tree one level per cell in the order of
+// `RuleTree::offsets`.
+@compute
+@workgroup_size(8, 8)
+fn cp_tree(@builtin(global_invocation_id) cell: vec3<u32>) {
+    if cell.x >= u32(grid_size.x) || cell.y >= u32(grid_size.y) {
+        return;
+    }
+
+    var moore = array<vec2<i32>, 9>(
+        vec2<i32>(-1, -1), vec2<i32>(1, -1), vec2<i32>(-1, 1), vec2<i32>(1, 1),
+        vec2<i32>(0, -1), vec2<i32>(-1, 0), vec2<i32>(1, 0), vec2<i32>(0, 1), vec2<i32>(0, 0)
+    );
+    var von_neumann = array<vec2<i32>, 5>(
+        vec2<i32>(0, -1), vec2<i32>(-1, 0), vec2<i32>(1, 0), vec2<i32>(0, 1), vec2<i32>(0, 0)
+    );
+    let c = vec2<i32>(cell.xy);
+    var node = rule.tree_root;
+    for (var i = 0u; i <= rule.tree_neighbours; i++) {
+        var offset: vec2<i32>;
+        if rule.tree_neighbours == 8u {
+            offset = moore[i];
+        } else {
+            offset = von_neumann[i];
         }
-        case 3u: {
-            cell_out[idx] = 1u;
+        var state = cell_state(c + offset);
+        // Out of range states would read another node, `RuleTree::next` reads them as 0 too.
+        if state >= rule.states {
+            state = 0u;
+        }
+        node = tree[node + state];
+    }
+    cell_out[cell_index(cell.xy)] = node;
+}
+
+// The bit-packed layout stepped by `cp_packed`: every row starts on a new word and bit `x % 32`
+// of word `x / 32` is the cell at `x`. Bits past the end of a row are always 0. Only for rules
+// with 2 states.
+
+fn row_words() -> i32 {
+    return (i32(grid_size.x) + 31) / 32;
+}
+
+fn packed_word(y: i32, word: i32) -> u32 {
+    if word < 0 || word >= row_words() {
+        return 0u;
+    }
+    return cell_in[y * row_words() + word];
+}
+
+fn packed_active(cell: vec2<i32>) -> u32 {
+    let p = resolve(cell);
+    if p.x < 0 {
+        return 0u;
+    }
+    return (packed_word(p.y, p.x >> 5u) >> u32(p.x & 31)) & 1u;
+}
+
+// The 32 cells of row `y` from column `x` on, bit `i` is the cell at `x + i`.
+fn packed_row(y: i32, x: i32) -> u32 {
+    let size = vec2<i32>(grid_size);
+    var row = y;
+    if (row < 0 || row >= size.y) && topology.y != 2u {
+        if topology.y == 0u {
+            return 0u;
+        }
+        row = (row + size.y) % size.y;
+    }
+
+    // Two neighbouring words shifted together, unless a wrapped or twisted edge is crossed.
+    // Padding and words past a dead edge read as 0.
+    let inside = row >= 0 && row < size.y;
+    if inside && (topology.x == 0u || (x >= 0 && x + 32 <= size.x)) {
+        let word = x >> 5u;
+        let shift = u32(x & 31);
+        var bits = packed_word(row, word) >> shift;
+        if shift != 0u {
+            bits |= packed_word(row, word + 1) << (32u - shift);
+        }
+        return bits;
+    }
+
+    var bits = 0u;
+    for (var i = 0; i < 32; i++) {
+        bits |= packed_active(vec2<i32>(x + i, y)) << u32(i);
+    }
+    return bits;
+}
+
+@compute
+@workgroup_size(8, 8)
+fn cp_packed(@builtin(global_invocation_id) id: vec3<u32>) {
+    let words = row_words();
+    if i32(id.x) >= words || id.y >= u32(grid_size.y) {
+        return;
+    }
+    let x = i32(id.x) * 32;
+    let y = i32(id.y);
+
+    // Four bit planes of the neighbour count, enough for 0..=8, each neighbour row added in
+    // with a ripple carry.
+    var count = array<u32, 4>(0u, 0u, 0u, 0u);
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            if dx != 0 || dy != 0 {
+                var carry = packed_row(y + dy, x + dx);
+                for (var i = 0; i < 4; i++) {
+                    let overflow = count[i] & carry;
+                    count[i] ^= carry;
+                    carry = overflow;
+                }
+            }
+        }
+    }
+
+    var born = 0u;
+    var survive = 0u;
+    for (var n = 0u; n <= 8u; n++) {
+        var equal = ~0u;
+        for (var i = 0u; i < 4u; i++) {
+            if ((n >> i) & 1u) == 1u {
+                equal &= count[i];
+            } else {
+                equal &= ~count[i];
+            }
+        }
+        if ((rule.birth >> n) & 1u) == 1u {
+            born |= equal;
+        }
+        if ((rule.survival >> n) & 1u) == 1u {
+            survive |= equal;
+        }
+    }
+
+    let idx = y * words + i32(id.x);
+    let centre = cell_in[idx];
+    var next = (centre & survive) | (~centre & born);
+    // Keep the padding past the end of the row dead.
+    let valid = u32(i32(grid_size.x) - x);
+    if valid < 32u {
+        next &= (1u << valid) - 1u;
+    }
+    cell_out[idx] = next;
+}
+
+// Larger than Life, stepped by `cp_larger` in workgroups of 8x8 cells that share a tile of
+// their cells and everything up to `MAX_RANGE` around them. Must match `larger::MAX_RANGE`.
+const MAX_RANGE: i32 = 10;
+const TILE: i32 = 28;
+// Prefix sums along the tile rows, `tile[y * (TILE + 1) + x]` counts the live cells left of
+// `x` in row `y`, so any run of a row is counted with one subtraction.
+var<workgroup> tile: array<u32, 812>;
+
+// Must match `Neighbourhood::half_width`.
+fn half_width(dy: i32) -> i32 {
+    let r = i32(rule.range);
+    switch rule.shape {
+        case 1u: {
+            return r - abs(dy);
+        }
+        case 2u: {
+            let limit = r * r + r - dy * dy;
+            var w = i32(sqrt(f32(limit)));
+            // Undo any rounding of the square root.
+            if (w + 1) * (w + 1) <= limit {
+                w += 1;
+            }
+            if w * w > limit {
+                w -= 1;
+            }
+            return w;
         }
         default: {
-            cell_out[idx] = 0u;
+            return r;
+        }
+    }
+}
+
+@compute
+@workgroup_size(8, 8)
+fn cp_larger(
+    @builtin(global_invocation_id) cell: vec3<u32>,
+    @builtin(local_invocation_id) local: vec3<u32>,
+    @builtin(local_invocation_index) index: u32,
+    @builtin(workgroup_id) group: vec3<u32>,
+) {
+    let r = i32(rule.range);
+    let side = 8 + 2 * r;
+    let origin = vec2<i32>(group.xy * 8u) - r;
+    // Each row is stored one to the right, leaving column 0 for the empty prefix.
+    for (var i = i32(index); i < side * side; i += 64) {
+        let t = vec2<i32>(i % side, i / side);
+        tile[t.y * (TILE + 1) + t.x + 1] = cell_active(origin + t);
+    }
+    workgroupBarrier();
+    if i32(index) < side {
+        let row = i32(index) * (TILE + 1);
+        tile[row] = 0u;
+        for (var x = 1; x <= side; x++) {
+            tile[row + x] += tile[row + x - 1];
         }
     }
+    workgroupBarrier();
+
+    if cell.x >= u32(grid_size.x) || cell.y >= u32(grid_size.y) {
+        return;
+    }
+    let c = vec2<i32>(local.xy) + r;
+    var count = 0u;
+    for (var dy = -r; dy <= r; dy++) {
+        let w = half_width(dy);
+        let row = (c.y + dy) * (TILE + 1);
+        count += tile[row + c.x + w + 1] - tile[row + c.x - w];
+    }
+
+    let idx = cell_index(cell.xy);
+    let state = cell_in[idx];
+    if state == 1u && rule.middle == 0u {
+        count -= 1u;
+    }
+    let born = count >= rule.birth_range.x && count <= rule.birth_range.y;
+    let survives = count >= rule.survival_range.x && count <= rule.survival_range.y;
+    if state == 0u {
+        cell_out[idx] = u32(born);
+    } else if state == 1u && survives {
+        cell_out[idx] = 1u;
+    } else {
+        cell_out[idx] = (state + 1u) % rule.states;
+    }
+}
+
+// Applies `edits` to `cell_out` in order. A single invocation, so edits of the same word never
+// race and the last one wins.
+@compute
+@workgroup_size(1)
+fn cp_edit() {
+    for (var i = 0u; i < edits[0]; i++) {
+        let word = edits[1u + 3u * i];
+        let mask = edits[2u + 3u * i];
+        cell_out[word] = (cell_out[word] & ~mask) | (edits[3u + 3u * i] & mask);
+    }
 }
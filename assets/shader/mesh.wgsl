@@ -14,29 +14,85 @@ var<uniform> proj: mat4x4<f32>;
 var<uniform> grid_size: vec2<f32>;
 @group(1)@binding(1)
 var<uniform> grid_pixel_size: vec2<f32>;
+// One word per cell, or bit-packed like `cp_packed` in compute.wgsl when `cell_format.x` is 1.
 @group(1)@binding(2)
 var<storage> grid_cell_arr: array<u32>;
+// x: 1 if `grid_cell_arr` is bit-packed, y: states of the rule, z: 1 to colour cells from
+// `palette`, w: `Tiling::uniform`.
+@group(1)@binding(3)
+var<uniform> cell_format: vec4<u32>;
+// 0xbbggrr per state, the `@COLORS` of a `.rule` file.
+@group(1)@binding(4)
+var<storage> palette: array<u32>;
+
+fn cell_state(cell: vec2<u32>) -> u32 {
+    let width = u32(grid_size.x);
+    if cell_format.x == 0u {
+        return grid_cell_arr[cell.y * width + cell.x];
+    }
+    let row_words = (width + 31u) / 32u;
+    return (grid_cell_arr[cell.y * row_words + cell.x / 32u] >> (cell.x % 32u)) & 1u;
+}
+
+// Live cells get a gradient across the grid, the dying states of a Generations rule fade from
+// orange to dark purple. Rules from `.rule` files have their own colours.
+fn state_color(state: u32, cell_idx: vec2<f32>) -> vec3<f32> {
+    if cell_format.z == 1u {
+        let rgb = palette[state];
+        return vec3<f32>(vec3<u32>(rgb, rgb >> 8u, rgb >> 16u) & vec3<u32>(255u)) / 255.0;
+    }
+    if state == 1u {
+        let c = cell_idx / grid_size;
+        return vec3<f32>(c, 1.0 - c.x);
+    }
+    let t = f32(state - 2u) / max(f32(cell_format.y) - 3.0, 1.0);
+    return mix(vec3<f32>(1.0, 0.55, 0.1), vec3<f32>(0.25, 0.05, 0.3), t);
+}
+
+// `Tiling::ROW_HEIGHT`.
+const ROW_HEIGHT: f32 = 0.8660254;
+
+// Bottom left corner of the cell's bounding box from the bottom left of the grid, must match
+// `Tiling::corner`. Row 0 is the top row, like in pattern files.
+fn cell_corner(cell: vec2<f32>) -> vec2<f32> {
+    let row = grid_size.y - 1.0 - cell.y;
+    switch cell_format.w {
+        case 1u: {
+            return vec2<f32>(cell.x + row / 2.0, row * ROW_HEIGHT);
+        }
+        case 2u, 3u: {
+            return vec2<f32>(cell.x / 2.0, row * ROW_HEIGHT);
+        }
+        default: {
+            return vec2<f32>(cell.x, row);
+        }
+    }
+}
 
 @vertex
 fn vs_main(in: VertexIn, @builtin(instance_index) instance_idx: u32) -> VertexOut {
-    let idx = f32(instance_idx);
-
+    // The size of the grid in world units, centred on the origin.
     let origin_pos = grid_pixel_size / 2.0 * -1.0;
-    let cell_size = grid_pixel_size / grid_size;
 
-    let cell_idx = vec2<f32>(idx % grid_size.x, floor(idx / grid_size.x));
+    let width = u32(grid_size.x);
+    let cell = vec2<u32>(instance_idx % width, instance_idx / width);
+    let cell_idx = vec2<f32>(cell);
 
     var out: VertexOut;
 
-    let cell_val = grid_cell_arr[instance_idx];
-    if cell_val == 0u {
+    let state = cell_state(cell);
+    if state == 0u {
         out.clip_pos = vec4<f32>(0.0);
         out.color = vec3<f32>(0.0);
     } else {
-        let cell_pos = cell_idx * cell_size * f32(grid_cell_arr[instance_idx]);
-        out.clip_pos = proj * vec4<f32>(in.pos / grid_size * grid_pixel_size + origin_pos + cell_pos, 0.0, 1.0);
-        let c = cell_idx / grid_size;
-        out.color = vec3<f32>(c, 1.0 - c.x);
+        var pos = in.pos;
+        // Every other triangle points down, turned around rather than mirrored to keep the
+        // winding the back face culling expects.
+        if cell_format.w >= 2u && (cell.x + cell.y) % 2u == 1u {
+            pos = vec2<f32>(1.0, ROW_HEIGHT) - pos;
+        }
+        out.clip_pos = proj * vec4<f32>(pos + origin_pos + cell_corner(cell_idx), 0.0, 1.0);
+        out.color = state_color(state, cell_idx);
     }
     return out;
 }
@@ -0,0 +1,41 @@
+//! Generations per second of the multithreaded `PackedSimulator` across grid sizes, with the
+//! one-cell-at-a-time `CpuSimulator` as a baseline on the smaller ones.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use game_of_life::{
+    cpu::CpuSimulator, grid::Grid, packed::PackedSimulator, rule::Rule, simulator::Simulator,
+};
+
+const GENERATIONS: u32 = 16;
+
+fn sizes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cpu");
+    group.throughput(Throughput::Elements(GENERATIONS as u64));
+    group.sample_size(10);
+    for size in [256, 1024, 4096] {
+        let grid = Grid::random(size, size, 0.3, 1);
+        let name = format!("{size}x{size}");
+
+        let mut packed = PackedSimulator::new(grid.clone(), Rule::LIFE);
+        group.bench_function(BenchmarkId::new("packed", &name), |b| {
+            b.iter(|| packed.step_n(GENERATIONS))
+        });
+
+        let mut single = PackedSimulator::new(grid.clone(), Rule::LIFE);
+        single.threads = 1;
+        group.bench_function(BenchmarkId::new("packed 1 thread", &name), |b| {
+            b.iter(|| single.step_n(GENERATIONS))
+        });
+
+        if size <= 1024 {
+            let mut cpu = CpuSimulator::new(grid, Rule::LIFE);
+            group.bench_function(BenchmarkId::new("reference", &name), |b| {
+                b.iter(|| cpu.step_n(GENERATIONS))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, sizes);
+criterion_main!(benches);
@@ -0,0 +1,38 @@
+//! Generations per second of `cp_main` against the bit-packed `cp_packed`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use game_of_life::{
+    gpu::{CellLayout, GpuSimulator},
+    grid::Grid,
+    rule::Rule,
+    simulator::Simulator,
+};
+
+const GENERATIONS: u32 = 64;
+
+fn layouts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("gpu");
+    group.throughput(Throughput::Elements(GENERATIONS as u64));
+    group.sample_size(10);
+    for size in [512, 2048] {
+        let grid = Grid::random(size, size, 0.3, 1);
+        for layout in [CellLayout::Word, CellLayout::Bit] {
+            let gpu = GpuSimulator::headless_with_layout(&grid, Rule::LIFE, layout);
+            let Some(mut gpu) = pollster::block_on(gpu) else {
+                eprintln!("no adapter, skipping gpu benchmark");
+                return;
+            };
+            let id = BenchmarkId::new(format!("{layout:?}"), format!("{size}x{size}"));
+            group.bench_function(id, |b| {
+                b.iter(|| {
+                    gpu.step_n(GENERATIONS);
+                    gpu.device.poll(wgpu::Maintain::Wait);
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, layouts);
+criterion_main!(benches);
@@ -0,0 +1,70 @@
+use glam::{Mat4, Vec2};
+
+/// 2D view onto the grid. World units are cells, with the grid centred on the origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    /// World position shown at the centre of the window.
+    pub translation: Vec2,
+    /// Screen pixels per world unit.
+    pub zoom: f32,
+}
+
+impl Camera {
+    pub const MIN_ZOOM: f32 = 1.0 / 64.0;
+    pub const MAX_ZOOM: f32 = 256.0;
+
+    pub fn new() -> Self {
+        Self {
+            translation: Vec2::ZERO,
+            zoom: 1.0,
+        }
+    }
+
+    pub fn proj(&self, width: u32, height: u32) -> Mat4 {
+        let half = Vec2::new(width as f32, height as f32) / 2.0 / self.zoom;
+        Mat4::orthographic_rh(
+            self.translation.x - half.x,
+            self.translation.x + half.x,
+            self.translation.y - half.y,
+            self.translation.y + half.y,
+            1.0,
+            -1.0,
+        )
+    }
+
+    /// World position under a screen position in physical pixels, origin top left.
+    pub fn screen_to_world(&self, x: f64, y: f64, width: u32, height: u32) -> Vec2 {
+        let offset = Vec2::new(
+            x as f32 - width as f32 / 2.0,
+            height as f32 / 2.0 - y as f32,
+        );
+        self.translation + offset / self.zoom
+    }
+
+    /// Move the view by a cursor drag of `dx`, `dy` screen pixels.
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.translation -= Vec2::new(dx as f32, -dy as f32) / self.zoom;
+    }
+
+    /// Scale by `factor`, keeping the world position under the cursor in place.
+    pub fn zoom_at(&mut self, factor: f32, x: f64, y: f64, width: u32, height: u32) {
+        let before = self.screen_to_world(x, y, width, height);
+        self.zoom = (self.zoom * factor).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        let after = self.screen_to_world(x, y, width, height);
+        self.translation += before - after;
+    }
+
+    /// Centre a `size` world-unit area and make it as large as fits the window.
+    pub fn fit(&mut self, size: Vec2, width: u32, height: u32) {
+        self.translation = Vec2::ZERO;
+        self.zoom = (width as f32 / size.x)
+            .min(height as f32 / size.y)
+            .clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,154 @@
+use std::path::PathBuf;
+
+use clap::{Parser, ValueEnum};
+
+use crate::{
+    rule::Rule,
+    scheduler::Rate,
+    soup::Symmetry,
+    topology::{BoundedGrid, Edge, Topology},
+};
+
+/// Conway's Game of Life and other Life-like rules on the GPU.
+#[derive(Parser, Debug)]
+#[command(version)]
+pub struct Args {
+    /// Pattern file to load (.rle, .cells, .lif, .life, .mc), placed in the centre of the grid.
+    pub pattern: Option<PathBuf>,
+
+    /// Rule such as B3/S23 or 23/3, an isotropic non-totalistic rule in Hensel notation such
+    /// as B3/S2-i34q, a Generations rule with a state count such as B2/S/C3 or 345/2/4, or a
+    /// Larger than Life rule such as R5,C0,M1,S34..58,B34..45,NM, or a Golly .rule file given
+    /// by path or by name from rules/ such as WireWorld, overrides the pattern's own rule. An
+    /// H suffix puts the cells on hexagons, L or LE on triangles with 12 or 3 neighbours, such
+    /// as B2/S34H. A Golly bounded grid suffix such as
+    /// `:T100,100`, `:K100*,100` or `:P100,100` sets the grid size and edges.
+    #[arg(short, long, value_parser = parse_rule)]
+    pub rule: Option<(Rule, Option<BoundedGrid>)>,
+
+    /// Grid width in cells, defaults to 40 or the pattern width if larger.
+    #[arg(long)]
+    pub width: Option<u32>,
+
+    /// Grid height in cells, defaults to 40 or the pattern height if larger.
+    #[arg(long)]
+    pub height: Option<u32>,
+
+    /// What happens at the grid edges, a torus unless the rule has a bounded grid suffix.
+    #[arg(long, value_enum)]
+    pub wrap: Option<Wrap>,
+
+    /// Run on an unbounded plane that grows with the pattern, the grid size only bounds the
+    /// initial soup.
+    #[arg(long, conflicts_with = "wrap")]
+    pub infinite: bool,
+
+    /// Step the unbounded plane with HashLife, headless only. Macrocell files are loaded and
+    /// written as trees, so they never have to fit in a grid.
+    #[arg(long, requires = "headless", conflicts_with = "wrap")]
+    pub hashlife: bool,
+
+    /// Fraction of live cells in the random soup used when no pattern is given.
+    #[arg(long, default_value_t = 0.3)]
+    pub density: f64,
+
+    /// Seed for the random soup, a random one is picked and logged if not given.
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Only fill a centred SIZE x SIZE square with the soup, the rest starts dead.
+    #[arg(long, value_name = "SIZE")]
+    pub soup_size: Option<u32>,
+
+    /// Symmetry of the soup: C1, C2, C4 or D8. Anything but C1 makes a square soup, the
+    /// whole grid's shorter side unless `--soup-size` is given.
+    #[arg(long, default_value = "C1")]
+    pub symmetry: Symmetry,
+
+    /// Generations per second, or `max` to step as fast as possible.
+    #[arg(long, default_value = "1", value_parser = parse_rate)]
+    pub rate: Rate,
+
+    /// Window size in pixels.
+    #[arg(long, default_value = "800x800", value_parser = parse_size)]
+    pub window_size: (u32, u32),
+
+    /// Start with the simulation paused.
+    #[arg(long)]
+    pub paused: bool,
+
+    /// Run without a window and write the result to `--output`.
+    #[arg(long, requires = "output")]
+    pub headless: bool,
+
+    /// Step headless runs on the multithreaded CPU backend instead of the GPU.
+    #[arg(long, requires = "headless")]
+    pub cpu: bool,
+
+    /// Threads the CPU backend splits the grid across, one per CPU by default.
+    #[arg(long, requires = "cpu")]
+    pub threads: Option<usize>,
+
+    /// Generations to run in headless mode.
+    #[arg(long, default_value_t = 0)]
+    pub generations: u32,
+
+    /// Where headless mode writes the final generation, format by extension.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Wrap {
+    /// Cells past the edges are dead.
+    Plane,
+    /// Opposite edges are joined.
+    Torus,
+    /// Left and right edges are joined, top and bottom with a twist.
+    Klein,
+    /// Both pairs of edges are joined with a twist, a projective plane.
+    CrossSurface,
+    /// Left and right edges are joined, top and bottom are dead.
+    Cylinder,
+    /// Top and bottom edges are joined, left and right are dead.
+    VerticalCylinder,
+}
+
+impl From<Wrap> for Topology {
+    fn from(wrap: Wrap) -> Self {
+        match wrap {
+            Wrap::Plane => Topology::PLANE,
+            Wrap::Torus => Topology::TORUS,
+            Wrap::Klein => Topology::KLEIN,
+            Wrap::CrossSurface => Topology::CROSS_SURFACE,
+            Wrap::Cylinder => Topology::CYLINDER,
+            Wrap::VerticalCylinder => Topology {
+                x: Edge::Dead,
+                y: Edge::Wrap,
+            },
+        }
+    }
+}
+
+fn parse_rule(s: &str) -> Result<(Rule, Option<BoundedGrid>), String> {
+    Rule::parse_bounded(s).map_err(|e| e.to_string())
+}
+
+fn parse_rate(s: &str) -> Result<Rate, String> {
+    match s {
+        "max" | "unlimited" => Ok(Rate::Unlimited),
+        _ => match s.parse::<f64>() {
+            Ok(rate) if rate > 0.0 => Ok(Rate::PerSecond(rate)),
+            _ => Err(format!("expected a positive number or `max`, found `{s}`")),
+        },
+    }
+}
+
+fn parse_size(s: &str) -> Result<(u32, u32), String> {
+    let error = || format!("expected WIDTHxHEIGHT, found `{s}`");
+    let (width, height) = s.split_once('x').ok_or_else(error)?;
+    match (width.parse(), height.parse()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(error()),
+    }
+}
@@ -1,52 +1,86 @@
-use std::time::Instant;
+use std::{sync::Arc, time::Instant};
 
-use glam::Mat4;
+use glam::{Mat4, Vec2};
+use image::RgbaImage;
 use wgpu::{
-    util::DeviceExt, Adapter, Backends, BindGroup, BindGroupDescriptor, BindGroupEntry,
+    util::DeviceExt, Adapter, Backends, BindGroup, BindGroupEntry, BindGroupLayout,
     BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBindingType, BufferUsages,
-    ColorWrites, ComputePipeline, Device, Features, Instance, Queue, RenderPipeline,
-    ShaderModuleDescriptor, ShaderStages, Surface, SurfaceConfiguration, TextureUsages,
+    ColorWrites, Device, Features, Instance, Queue, RenderPipeline, ShaderStages, Surface,
+    SurfaceConfiguration, Texture, TextureFormat, TextureUsages,
 };
 use winit::{
-    event::{ElementState, VirtualKeyCode},
+    event::{ElementState, MouseButton, MouseScrollDelta, VirtualKeyCode},
     event_loop::{ControlFlow, EventLoop},
     window::Window,
 };
 
 use crate::{
-    grid::{self, Grid},
+    camera::Camera,
+    edit::{self, Stroke, Tool},
+    gpu::{self, CellLayout, GpuSimulator},
+    grid::Grid,
+    rule::Rule,
+    scheduler::{Rate, Scheduler},
+    simulator::{Simulator, UnboundedSimulator},
+    sparse::{render::ChunkRenderer, SparseLife},
     vertex::Vertex,
 };
 
+/// Where `Core::render` draws to.
+pub enum RenderTarget {
+    Surface(Surface),
+    Texture(Texture),
+}
+
 pub struct Core {
     pub instance: Instance,
-    pub surface: Surface,
+    pub target: RenderTarget,
     pub adapter: Adapter,
-    pub device: Device,
-    pub queue: Queue,
+    pub device: Arc<Device>,
+    pub queue: Arc<Queue>,
     pub surface_config: SurfaceConfiguration,
 
+    pub camera: Camera,
+    pub proj: Mat4,
     pub proj_bind_group: BindGroup,
     pub proj_buffer: Buffer,
 
+    /// World units the grid covers, `Tiling::size`.
+    pub grid_pixel_size_buffer: Buffer,
+    /// Whether the cells are bit-packed, how many states the rule has, whether it has a
+    /// palette and the shape of the cells, for `mesh.wgsl`.
+    pub cell_format_buffer: Buffer,
+    /// Colour of each state of a `.rule` file's rule, from its `@COLORS`.
+    pub palette_buffer: Buffer,
+
+    pub grid_bind_group_layout: BindGroupLayout,
     pub grid_bind_group_arr: [BindGroup; 2],
 
     pub vertex_arr: Vec<Vertex>,
     pub vertex_buffer: Buffer,
 
     pub render_pipline: RenderPipeline,
-    pub compute_pipline: ComputePipeline,
-    pub compute_bind_group_arr: [BindGroup; 2],
+    pub simulator: GpuSimulator,
+    pub chunk_renderer: ChunkRenderer,
+    /// Set by `use_sparse`, then this unbounded plane is stepped and drawn instead of the grid.
+    pub sparse: Option<SparseLife>,
 
     pub grid: Grid,
 
-    pub start_time: Instant,
-    pub last_cell_swap_time: Instant,
-    pub step: u32,
+    pub scheduler: Scheduler,
+    /// Soup used by `reseed`.
+    pub density: f64,
+    pub seed: u64,
+
+    /// Last cursor position in physical pixels, origin top left.
+    pub cursor: Option<(f64, f64)>,
+    pub stroke: Option<Stroke>,
+    /// Middle button held, cursor moves pan the camera.
+    pub panning: bool,
 }
 
 impl Core {
-    pub async fn new(_event_loop: &EventLoop<()>, window: &Window) -> Self {
+    pub async fn new(_event_loop: &EventLoop<()>, window: &Window, grid: Grid, rule: Rule) -> Self {
         let window_size = window.inner_size();
 
         let instance = Instance::new(wgpu::InstanceDescriptor {
@@ -65,29 +99,74 @@ impl Core {
             .await
             .unwrap();
 
+        let surface_caps = surface.get_capabilities(&adapter);
+        let surface_config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format: surface_caps.formats[0],
+            width: window_size.width,
+            height: window_size.height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: surface_caps.alpha_modes[0],
+            view_formats: vec![],
+        };
+
+        Self::build(instance, Some(surface), adapter, surface_config, grid, rule).await
+    }
+
+    /// Renders into a `width` x `height` texture instead of a window. Any adapter will do,
+    /// including a software one, `None` if there is none at all.
+    pub async fn headless(width: u32, height: u32, grid: Grid, rule: Rule) -> Option<Self> {
+        let instance = Instance::new(wgpu::InstanceDescriptor {
+            backends: Backends::all(),
+            dx12_shader_compiler: Default::default(),
+        });
+
+        let adapter = gpu::request_headless_adapter(&instance).await?;
+
+        let surface_config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            format: TextureFormat::Rgba8UnormSrgb,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+        };
+
+        let mut core = Self::build(instance, None, adapter, surface_config, grid, rule).await;
+        core.resize(width, height);
+        Some(core)
+    }
+
+    async fn build(
+        instance: Instance,
+        surface: Option<Surface>,
+        adapter: Adapter,
+        surface_config: SurfaceConfiguration,
+        grid: Grid,
+        rule: Rule,
+    ) -> Self {
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: Some("Device"),
                     features: Features::empty(),
-                    limits: Default::default(),
+                    limits: adapter.limits(),
                 },
                 None,
             )
             .await
             .unwrap();
+        let device = Arc::new(device);
+        let queue = Arc::new(queue);
 
-        let surface_caps = surface.get_capabilities(&adapter);
-        let surface_config = SurfaceConfiguration {
-            usage: TextureUsages::RENDER_ATTACHMENT,
-            format: surface_caps.formats[0],
-            width: window_size.width,
-            height: window_size.height,
-            present_mode: wgpu::PresentMode::Fifo,
-            alpha_mode: surface_caps.alpha_modes[0],
-            view_formats: vec![],
+        let target = match surface {
+            Some(surface) => {
+                surface.configure(&device, &surface_config);
+                RenderTarget::Surface(surface)
+            }
+            None => RenderTarget::Texture(Self::create_target_texture(&device, &surface_config)),
         };
-        surface.configure(&device, &surface_config);
 
         let proj_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -104,7 +183,11 @@ impl Core {
                 }],
             });
 
-        let proj = Mat4::orthographic_rh(-1.0, 1.0, -1.0, 1.0, 1.0, -1.0);
+        // World units the cells cover in the rule's tiling.
+        let world_size = rule.tiling.size((grid.width, grid.height));
+        let mut camera = Camera::new();
+        camera.fit(world_size, surface_config.width, surface_config.height);
+        let proj = camera.proj(surface_config.width, surface_config.height);
         let proj_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Proj Buffer"),
             contents: bytemuck::cast_slice(&proj.to_cols_array_2d()),
@@ -153,94 +236,9 @@ impl Core {
                         },
                         count: None,
                     },
-                ],
-            });
-
-        let grid = Grid::new();
-        let grid_size_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Grid Size Buffer"),
-            contents: bytemuck::cast_slice(&[grid::GRID_SIZE as f32, grid::GRID_SIZE as f32]),
-            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-        });
-        let grid_pixel_size_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Grid Pilex Size"),
-            contents: bytemuck::cast_slice(&[
-                grid::GRID_PIXEL_SIZE as f32,
-                grid::GRID_PIXEL_SIZE as f32,
-            ]),
-            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-        });
-        let grid_cell_buffer_arr = [
-            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Grid Cell Buffer"),
-                contents: bytemuck::cast_slice(&grid.cell_arr),
-                usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
-            }),
-            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Grid Cell Buffer"),
-                contents: bytemuck::cast_slice(&grid.cell_arr),
-                usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
-            }),
-        ];
-
-        let grid_bind_group_arr = [
-            device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("Grid Bind Group"),
-                layout: &grid_bind_group_layout,
-                entries: &[
-                    BindGroupEntry {
-                        binding: 0,
-                        resource: BindingResource::Buffer(
-                            grid_size_buffer.as_entire_buffer_binding(),
-                        ),
-                    },
-                    BindGroupEntry {
-                        binding: 1,
-                        resource: BindingResource::Buffer(
-                            grid_pixel_size_buffer.as_entire_buffer_binding(),
-                        ),
-                    },
-                    BindGroupEntry {
-                        binding: 2,
-                        resource: BindingResource::Buffer(
-                            grid_cell_buffer_arr[0].as_entire_buffer_binding(),
-                        ),
-                    },
-                ],
-            }),
-            device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("Grid Bind Group"),
-                layout: &grid_bind_group_layout,
-                entries: &[
-                    BindGroupEntry {
-                        binding: 0,
-                        resource: BindingResource::Buffer(
-                            grid_size_buffer.as_entire_buffer_binding(),
-                        ),
-                    },
-                    BindGroupEntry {
-                        binding: 1,
-                        resource: BindingResource::Buffer(
-                            grid_pixel_size_buffer.as_entire_buffer_binding(),
-                        ),
-                    },
-                    BindGroupEntry {
-                        binding: 2,
-                        resource: BindingResource::Buffer(
-                            grid_cell_buffer_arr[1].as_entire_buffer_binding(),
-                        ),
-                    },
-                ],
-            }),
-        ];
-
-        let compute_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Compute Bind Group Layout"),
-                entries: &[
                     BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: ShaderStages::VERTEX | ShaderStages::COMPUTE,
+                        binding: 3,
+                        visibility: ShaderStages::VERTEX,
                         ty: BindingType::Buffer {
                             ty: BufferBindingType::Uniform,
                             has_dynamic_offset: false,
@@ -249,8 +247,8 @@ impl Core {
                         count: None,
                     },
                     BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: ShaderStages::VERTEX | ShaderStages::COMPUTE,
+                        binding: 4,
+                        visibility: ShaderStages::VERTEX,
                         ty: BindingType::Buffer {
                             ty: BufferBindingType::Storage { read_only: true },
                             has_dynamic_offset: false,
@@ -258,22 +256,34 @@ impl Core {
                         },
                         count: None,
                     },
-                    BindGroupLayoutEntry {
-                        binding: 2,
-                        visibility: ShaderStages::COMPUTE,
-                        ty: BindingType::Buffer {
-                            ty: BufferBindingType::Storage { read_only: false },
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    },
                 ],
             });
 
-        // let compute_bind_groups
+        let simulator = GpuSimulator::new(device.clone(), queue.clone(), &grid, rule);
+        let grid_pixel_size_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Grid Pilex Size"),
+            contents: bytemuck::cast_slice(&world_size.to_array()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let cell_format_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Cell Format Buffer"),
+            contents: bytemuck::cast_slice(&Self::cell_format(&simulator)),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let palette_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Palette Buffer"),
+            contents: bytemuck::cast_slice(&Self::palette(&simulator.rule)),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        });
+        let grid_bind_group_arr = Self::create_grid_bind_groups(
+            &device,
+            &grid_bind_group_layout,
+            &simulator,
+            &grid_pixel_size_buffer,
+            [&cell_format_buffer, &palette_buffer],
+        );
 
-        let vertex_arr = Vertex::rect();
+        let vertex_arr = rule.tiling.vertices();
 
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex Buffer"),
@@ -288,17 +298,9 @@ impl Core {
                 push_constant_ranges: &[],
             });
 
-        let compute_pipline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Compute Pipline Layout"),
-                bind_group_layouts: &[&compute_bind_group_layout],
-                push_constant_ranges: &[],
-            });
-
-        let mesh_wgsl = std::fs::read_to_string("assets/shader/mesh.wgsl").unwrap();
         let mesh_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Mesh Shader"),
-            source: wgpu::ShaderSource::Wgsl(mesh_wgsl.into()),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../assets/shader/mesh.wgsl").into()),
         });
 
         let render_pipline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
@@ -336,141 +338,451 @@ impl Core {
             multiview: None,
         });
 
-        let compute_wgsl = std::fs::read_to_string("assets/shader/compute.wgsl").unwrap();
-        let compute_shader = device.create_shader_module(ShaderModuleDescriptor {
-            label: Some("Compute Shader"),
-            source: wgpu::ShaderSource::Wgsl(compute_wgsl.into()),
-        });
+        let chunk_renderer =
+            ChunkRenderer::new(&device, &proj_bind_group_layout, surface_config.format);
 
-        let compute_pipline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Compute Pipline"),
-            layout: Some(&compute_pipline_layout),
-            module: &compute_shader,
-            entry_point: "cp_main",
-        });
+        Self {
+            instance,
+            target,
+            adapter,
+            device,
+            queue,
+            surface_config,
+            camera,
+            proj,
+            proj_bind_group,
+            proj_buffer,
+            grid_pixel_size_buffer,
+            cell_format_buffer,
+            palette_buffer,
+            grid_bind_group_layout,
+            grid_bind_group_arr,
+            vertex_arr,
+            vertex_buffer,
+            render_pipline,
+            simulator,
+            chunk_renderer,
+            sparse: None,
+            grid,
+            scheduler: Scheduler::new(Rate::PerSecond(1.0)),
+            density: 0.3,
+            seed: 0,
+            cursor: None,
+            stroke: None,
+            panning: false,
+        }
+    }
+
+    pub fn set_vertex_arr(&mut self, vertex_arr: Vec<Vertex>) {
+        self.vertex_arr = vertex_arr;
+        self.vertex_buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Vertex Buffer"),
+                contents: bytemuck::cast_slice(&self.vertex_arr),
+                usage: BufferUsages::VERTEX,
+            });
+    }
 
-        let compute_bind_group_arr = [
-            device.create_bind_group(&BindGroupDescriptor {
-                label: Some("Compute Bind Group A"),
-                layout: &compute_bind_group_layout,
+    /// One bind group per cell buffer of `simulator`, for drawing either generation.
+    fn create_grid_bind_groups(
+        device: &Device,
+        layout: &BindGroupLayout,
+        simulator: &GpuSimulator,
+        grid_pixel_size_buffer: &Buffer,
+        [cell_format_buffer, palette_buffer]: [&Buffer; 2],
+    ) -> [BindGroup; 2] {
+        simulator.grid_cell_buffer_arr.each_ref().map(|cells| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Grid Bind Group"),
+                layout,
                 entries: &[
                     BindGroupEntry {
                         binding: 0,
                         resource: BindingResource::Buffer(
-                            grid_size_buffer.as_entire_buffer_binding(),
+                            simulator.grid_size_buffer.as_entire_buffer_binding(),
                         ),
                     },
                     BindGroupEntry {
                         binding: 1,
                         resource: BindingResource::Buffer(
-                            grid_cell_buffer_arr[0].as_entire_buffer_binding(),
+                            grid_pixel_size_buffer.as_entire_buffer_binding(),
                         ),
                     },
                     BindGroupEntry {
                         binding: 2,
-                        resource: BindingResource::Buffer(
-                            grid_cell_buffer_arr[1].as_entire_buffer_binding(),
-                        ),
+                        resource: BindingResource::Buffer(cells.as_entire_buffer_binding()),
                     },
-                ],
-            }),
-            device.create_bind_group(&BindGroupDescriptor {
-                label: Some("Compute Bind Group B"),
-                layout: &compute_bind_group_layout,
-                entries: &[
                     BindGroupEntry {
-                        binding: 0,
+                        binding: 3,
                         resource: BindingResource::Buffer(
-                            grid_size_buffer.as_entire_buffer_binding(),
+                            cell_format_buffer.as_entire_buffer_binding(),
                         ),
                     },
                     BindGroupEntry {
-                        binding: 1,
+                        binding: 4,
                         resource: BindingResource::Buffer(
-                            grid_cell_buffer_arr[1].as_entire_buffer_binding(),
-                        ),
-                    },
-                    BindGroupEntry {
-                        binding: 2,
-                        resource: BindingResource::Buffer(
-                            grid_cell_buffer_arr[0].as_entire_buffer_binding(),
+                            palette_buffer.as_entire_buffer_binding(),
                         ),
                     },
                 ],
-            }),
-        ];
+            })
+        })
+    }
 
-        Self {
-            instance,
-            surface,
-            adapter,
-            device,
-            queue,
-            surface_config,
-            proj_bind_group,
-            proj_buffer,
-            grid_bind_group_arr,
-            vertex_arr: vertex_arr.into(),
-            vertex_buffer,
-            render_pipline,
-            compute_bind_group_arr,
-            compute_pipline,
-            grid,
-            start_time: Instant::now(),
-            last_cell_swap_time: Instant::now(),
-            step: 0,
+    fn cell_format(simulator: &GpuSimulator) -> [u32; 4] {
+        [
+            (simulator.layout == CellLayout::Bit) as u32,
+            simulator.rule.states,
+            simulator.rule.tree.is_some() as u32,
+            simulator.rule.tiling.uniform(),
+        ]
+    }
+
+    /// `0xbbggrr` per state of a `.rule` file's rule, unused by other rules.
+    fn palette(rule: &Rule) -> [u32; Rule::MAX_STATES as usize] {
+        let mut palette = [0; Rule::MAX_STATES as usize];
+        if let Some(tree) = &rule.tree {
+            for (entry, [r, g, b]) in palette.iter_mut().zip(&tree.colors) {
+                *entry = u32::from_le_bytes([*r, *g, *b, 0]);
+            }
         }
+        palette
     }
 
-    pub fn set_vertex_arr(&mut self, vertex_arr: Vec<Vertex>) {
-        self.vertex_arr = vertex_arr;
-        self.vertex_buffer = self
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Vertex Buffer"),
-                contents: bytemuck::cast_slice(&self.vertex_arr),
-                usage: BufferUsages::VERTEX,
-            });
+    /// Change the rule, moving the cells to a layout that holds all of its states if the
+    /// current one cannot, and refitting the view if the cells change shape.
+    pub fn set_rule(&mut self, rule: Rule) {
+        let retiled = rule.tiling != self.simulator.rule.tiling;
+        self.queue.write_buffer(
+            &self.palette_buffer,
+            0,
+            bytemuck::cast_slice(&Self::palette(&rule)),
+        );
+        let layout = self.simulator.layout;
+        self.simulator.set_rule(rule);
+        if self.simulator.layout != layout {
+            // The cells moved to new buffers.
+            self.grid_bind_group_arr = Self::create_grid_bind_groups(
+                &self.device,
+                &self.grid_bind_group_layout,
+                &self.simulator,
+                &self.grid_pixel_size_buffer,
+                [&self.cell_format_buffer, &self.palette_buffer],
+            );
+        }
+        self.queue.write_buffer(
+            &self.cell_format_buffer,
+            0,
+            bytemuck::cast_slice(&Self::cell_format(&self.simulator)),
+        );
+        if retiled {
+            self.set_vertex_arr(self.simulator.rule.tiling.vertices());
+            self.queue.write_buffer(
+                &self.grid_pixel_size_buffer,
+                0,
+                bytemuck::cast_slice(&self.grid_world_size().to_array()),
+            );
+            self.fit_grid();
+        }
     }
 
-    fn resize(&mut self, width: u32, height: u32) {
+    /// World units the cells of the grid cover in the current rule's tiling.
+    fn grid_world_size(&self) -> Vec2 {
+        let size = (self.grid.width, self.grid.height);
+        self.simulator.rule.tiling.size(size)
+    }
+
+    fn create_target_texture(device: &Device, config: &SurfaceConfiguration) -> Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Target Texture"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: config.usage,
+            view_formats: &[],
+        })
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
         self.surface_config.width = width;
         self.surface_config.height = height;
-        self.surface.configure(&self.device, &self.surface_config);
-
-        let proj = Mat4::orthographic_rh(
-            -(width as f32 / 2.0),
-            width as f32 / 2.0,
-            -(height as f32 / 2.0),
-            height as f32 / 2.0,
-            1.0,
-            -1.0,
-        );
+        match &mut self.target {
+            RenderTarget::Surface(surface) => surface.configure(&self.device, &self.surface_config),
+            RenderTarget::Texture(texture) => {
+                *texture = Self::create_target_texture(&self.device, &self.surface_config)
+            }
+        }
 
+        self.update_proj();
+    }
+
+    fn update_proj(&mut self) {
+        self.proj = self
+            .camera
+            .proj(self.surface_config.width, self.surface_config.height);
         self.queue.write_buffer(
             &self.proj_buffer,
             0,
-            bytemuck::cast_slice(&proj.to_cols_array_2d()),
+            bytemuck::cast_slice(&self.proj.to_cols_array_2d()),
         );
     }
 
+    pub fn title(&self) -> String {
+        let paused = if self.scheduler.paused {
+            " - paused"
+        } else {
+            ""
+        };
+        match &self.sparse {
+            Some(life) => format!(
+                "game of life - generation {} - population {} - {}{}",
+                life.generation,
+                life.population(),
+                self.scheduler.rate,
+                paused
+            ),
+            None => format!(
+                "game of life - generation {} - {}{}",
+                self.simulator.generation, self.scheduler.rate, paused
+            ),
+        }
+    }
+
+    /// Replace the cells with a random soup from the next seed and start counting generations
+    /// over.
+    pub fn reseed(&mut self) {
+        self.seed = self.seed.wrapping_add(1);
+        log::info!("reseeded with seed {}", self.seed);
+        let grid = Grid::random(self.grid.width, self.grid.height, self.density, self.seed);
+        if let Some(life) = &mut self.sparse {
+            let origin = (-(grid.width as i64) / 2, -(grid.height as i64) / 2);
+            *life = SparseLife::from_grid(&grid, life.rule, origin);
+            return;
+        }
+        self.simulator.write_cells(&grid);
+        self.simulator.generation = 0;
+    }
+
+    /// Step and draw `life` on an unbounded plane instead of the grid.
+    pub fn use_sparse(&mut self, life: SparseLife) {
+        self.sparse = Some(life);
+        self.fit_grid();
+    }
+
+    fn handle_key(&mut self, key: VirtualKeyCode) {
+        match key {
+            VirtualKeyCode::Space => self.scheduler.paused = !self.scheduler.paused,
+            VirtualKeyCode::N | VirtualKeyCode::Right if self.scheduler.paused => self.step(1),
+            VirtualKeyCode::Equals | VirtualKeyCode::Plus | VirtualKeyCode::NumpadAdd => {
+                self.scheduler.set_rate(self.scheduler.rate.faster())
+            }
+            VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract => {
+                self.scheduler.set_rate(self.scheduler.rate.slower())
+            }
+            VirtualKeyCode::R => self.reseed(),
+            VirtualKeyCode::F => self.fit_grid(),
+            _ => {}
+        }
+    }
+
+    /// Zoom so the whole grid, or every live cell of the plane, fills the window.
+    pub fn fit_grid(&mut self) {
+        let (size, centre) = match self.sparse.as_ref().and_then(|life| life.bounding_box()) {
+            Some(((min_x, min_y), (max_x, max_y))) => (
+                Vec2::new((max_x - min_x + 1) as f32, (max_y - min_y + 1) as f32),
+                // Row y covers world -y - 1..-y.
+                Vec2::new(
+                    (min_x + max_x + 1) as f32 / 2.0,
+                    -(min_y + max_y + 1) as f32 / 2.0,
+                ),
+            ),
+            None => (self.grid_world_size(), Vec2::ZERO),
+        };
+        self.camera
+            .fit(size, self.surface_config.width, self.surface_config.height);
+        self.camera.translation = centre;
+        self.update_proj();
+    }
+
+    /// Cell under a cursor position in physical pixels, `None` outside the grid.
+    pub fn cursor_to_cell(&self, x: f64, y: f64) -> Option<(u32, u32)> {
+        let pos = self.camera.screen_to_world(
+            x,
+            y,
+            self.surface_config.width,
+            self.surface_config.height,
+        );
+
+        let size = (self.grid.width, self.grid.height);
+        let tiling = self.simulator.rule.tiling;
+        tiling.cell_at(pos + tiling.size(size) / 2.0, size)
+    }
+
+    /// Cell under the cursor on whichever of the grid or the plane is shown.
+    pub fn cursor_cell(&self, x: f64, y: f64) -> Option<(i64, i64)> {
+        if self.sparse.is_none() {
+            return self.cursor_to_cell(x, y).map(|(x, y)| (x as i64, y as i64));
+        }
+        let pos = self.camera.screen_to_world(
+            x,
+            y,
+            self.surface_config.width,
+            self.surface_config.height,
+        );
+        Some((pos.x.floor() as i64, -(pos.y.floor() as i64) - 1))
+    }
+
+    fn get_cell(&self, (x, y): (i64, i64)) -> u32 {
+        match &self.sparse {
+            Some(life) => life.get(x, y) as u32,
+            None => pollster::block_on(self.simulator.get_cell(x as u32, y as u32)),
+        }
+    }
+
+    /// Set every cell of `cells` to `v`, in one batch on the GPU.
+    fn set_cells(&mut self, cells: &[(i64, i64)], v: u32) {
+        match &mut self.sparse {
+            Some(life) => {
+                for &(x, y) in cells {
+                    life.set(x, y, v != 0);
+                }
+            }
+            None => {
+                let edits: Vec<_> = cells
+                    .iter()
+                    .map(|&(x, y)| (x as u32, y as u32, v))
+                    .collect();
+                self.simulator.set_cells(&edits);
+            }
+        }
+    }
+
+    fn mouse_input(&mut self, state: ElementState, button: MouseButton) {
+        let tool = match button {
+            MouseButton::Left => Tool::Draw,
+            MouseButton::Right => Tool::Erase,
+            MouseButton::Middle => {
+                self.panning = state == ElementState::Pressed;
+                return;
+            }
+            _ => return,
+        };
+        match state {
+            ElementState::Pressed => {
+                let Some(cell) = self.cursor.and_then(|(x, y)| self.cursor_cell(x, y)) else {
+                    return;
+                };
+                if tool == Tool::Erase {
+                    self.set_cells(&[cell], 0);
+                }
+                self.stroke = Some(Stroke::new(tool, cell));
+            }
+            ElementState::Released => {
+                let Some(stroke) = self.stroke.take_if(|stroke| stroke.tool == tool) else {
+                    return;
+                };
+                if stroke.tool == Tool::Draw && !stroke.moved {
+                    // Dying cells of a Generations rule come back to life.
+                    let v = self.get_cell(stroke.start);
+                    self.set_cells(&[stroke.start], (v != 1) as u32);
+                }
+            }
+        }
+    }
+
+    fn cursor_moved(&mut self, x: f64, y: f64) {
+        if let (true, Some((last_x, last_y))) = (self.panning, self.cursor) {
+            self.camera.pan(x - last_x, y - last_y);
+            self.update_proj();
+        }
+        self.cursor = Some((x, y));
+        let Some(cell) = self.cursor_cell(x, y) else {
+            return;
+        };
+        let Some(stroke) = self.stroke.as_mut() else {
+            return;
+        };
+        if cell == stroke.last {
+            return;
+        }
+
+        let v = match stroke.tool {
+            Tool::Draw => 1,
+            Tool::Erase => 0,
+        };
+        stroke.moved = true;
+        let last = std::mem::replace(&mut stroke.last, cell);
+        self.set_cells(&edit::line(last, cell), v);
+    }
+
+    fn mouse_wheel(&mut self, delta: MouseScrollDelta) {
+        let lines = match delta {
+            MouseScrollDelta::LineDelta(_, y) => y,
+            MouseScrollDelta::PixelDelta(pos) => pos.y as f32 / 40.0,
+        };
+        let Some((x, y)) = self.cursor else {
+            return;
+        };
+        self.camera.zoom_at(
+            1.2f32.powf(lines),
+            x,
+            y,
+            self.surface_config.width,
+            self.surface_config.height,
+        );
+        self.update_proj();
+    }
+
     pub fn update(&mut self) {
-        let last_time = (Instant::now() - self.last_cell_swap_time).as_secs_f32();
-        if last_time >= 1.0 {
-            self.last_cell_swap_time = Instant::now();
-            let time = (Instant::now() - self.start_time).as_secs_f32().floor() as u32;
+        let due = self.scheduler.tick(Instant::now());
+        if due > 0 {
+            self.step(due);
+        }
+    }
 
-            self.step = time % 2;
+    /// Advance `n` generations right away, regardless of `update`'s timing.
+    pub fn step(&mut self, n: u32) {
+        match &mut self.sparse {
+            Some(life) => life.step_n(n.into()),
+            None => self.simulator.step_n(n),
         }
     }
 
-    pub fn render(&self) {
+    /// Snapshot of the generation currently on the GPU.
+    pub async fn read_cells(&self) -> Grid {
+        self.simulator.read().await
+    }
+
+    pub fn render(&mut self) {
+        if let Some(life) = &self.sparse {
+            self.chunk_renderer.prepare(
+                &self.device,
+                &self.queue,
+                life,
+                &self.camera,
+                self.surface_config.width,
+                self.surface_config.height,
+            );
+        }
         let render_pipline = &self.render_pipline;
 
-        let current_texture = self.surface.get_current_texture().unwrap();
-        let texture_view = current_texture
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        let current_texture = match &self.target {
+            RenderTarget::Surface(surface) => Some(surface.get_current_texture().unwrap()),
+            RenderTarget::Texture(_) => None,
+        };
+        let texture_view = match (&current_texture, &self.target) {
+            (Some(current_texture), _) => &current_texture.texture,
+            (None, RenderTarget::Texture(texture)) => texture,
+            (None, RenderTarget::Surface(_)) => unreachable!(),
+        }
+        .create_view(&wgpu::TextureViewDescriptor::default());
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
@@ -493,56 +805,124 @@ impl Core {
                 depth_stencil_attachment: None,
             });
 
-            render_pass.set_pipeline(render_pipline);
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
             render_pass.set_bind_group(0, &self.proj_bind_group, &[]);
-            render_pass.set_bind_group(1, &self.grid_bind_group_arr[self.step as usize], &[]);
-            render_pass.draw(
-                0..self.vertex_arr.len() as _,
-                0..grid::GRID_SIZE * grid::GRID_SIZE,
-            );
+            if self.sparse.is_some() {
+                self.chunk_renderer.draw(&mut render_pass);
+            } else {
+                render_pass.set_pipeline(render_pipline);
+                render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+                render_pass.set_bind_group(
+                    1,
+                    &self.grid_bind_group_arr[self.simulator.step as usize],
+                    &[],
+                );
+                render_pass.draw(0..self.vertex_arr.len() as _, 0..self.grid.len());
+            }
         }
 
-        {
-            let mut compute_pass =
-                encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
-            compute_pass.set_pipeline(&self.compute_pipline);
-            compute_pass.set_bind_group(0, &self.compute_bind_group_arr[self.step as usize], &[]);
-            let workgroup_count = (grid::GRID_SIZE as f32 / 8.0).ceil();
-            compute_pass.dispatch_workgroups(workgroup_count as _, workgroup_count as _, 1);
+        self.queue.submit(std::iter::once(encoder.finish()));
+        if let Some(current_texture) = current_texture {
+            current_texture.present();
         }
+    }
 
+    /// Copy the last rendered frame of a headless `Core` out of its target texture.
+    pub fn read_frame(&self) -> Option<RgbaImage> {
+        let RenderTarget::Texture(texture) = &self.target else {
+            return None;
+        };
+        let (width, height) = (self.surface_config.width, self.surface_config.height);
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = (width * 4).div_ceil(align) * align;
+
+        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Frame Staging Buffer"),
+            size: (padded_bytes_per_row * height) as u64,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &staging_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
         self.queue.submit(std::iter::once(encoder.finish()));
-        current_texture.present();
+
+        let slice = staging_buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |res| tx.send(res).unwrap());
+        self.device.poll(wgpu::Maintain::Wait);
+        rx.recv().unwrap().ok()?;
+
+        let data = slice.get_mapped_range();
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for row in data.chunks(padded_bytes_per_row as usize) {
+            pixels.extend_from_slice(&row[..(width * 4) as usize]);
+        }
+        drop(data);
+        staging_buffer.unmap();
+
+        RgbaImage::from_raw(width, height, pixels)
     }
 
     pub fn block_loop(mut self, event_loop: EventLoop<()>, window: Window) {
+        let mut title = String::new();
         event_loop.run(move |event, _, control_flow| match event {
             winit::event::Event::MainEventsCleared => window.request_redraw(),
-            winit::event::Event::RedrawRequested(window_id) => {
-                if window_id == window.id() {
-                    self.update();
-                    self.render();
+            winit::event::Event::RedrawRequested(window_id) if window_id == window.id() => {
+                self.update();
+                self.render();
+
+                let new_title = self.title();
+                if new_title != title {
+                    window.set_title(&new_title);
+                    title = new_title;
                 }
             }
-            winit::event::Event::WindowEvent { window_id, event } => {
-                if window_id == window.id() {
-                    match event {
-                        winit::event::WindowEvent::Resized(new_size) => {
-                            self.resize(new_size.width, new_size.height);
-                        }
-                        winit::event::WindowEvent::CloseRequested
-                        | winit::event::WindowEvent::KeyboardInput {
-                            input:
-                                winit::event::KeyboardInput {
-                                    state: ElementState::Released,
-                                    virtual_keycode: Some(VirtualKeyCode::Escape),
-                                    ..
-                                },
-                            ..
-                        } => *control_flow = ControlFlow::Exit,
-                        _ => {}
+            winit::event::Event::WindowEvent { window_id, event } if window_id == window.id() => {
+                match event {
+                    winit::event::WindowEvent::Resized(new_size) => {
+                        self.resize(new_size.width, new_size.height);
+                    }
+                    winit::event::WindowEvent::CloseRequested
+                    | winit::event::WindowEvent::KeyboardInput {
+                        input:
+                            winit::event::KeyboardInput {
+                                state: ElementState::Released,
+                                virtual_keycode: Some(VirtualKeyCode::Escape),
+                                ..
+                            },
+                        ..
+                    } => *control_flow = ControlFlow::Exit,
+                    winit::event::WindowEvent::KeyboardInput {
+                        input:
+                            winit::event::KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(key),
+                                ..
+                            },
+                        ..
+                    } => self.handle_key(key),
+                    winit::event::WindowEvent::CursorMoved { position, .. } => {
+                        self.cursor_moved(position.x, position.y);
+                    }
+                    winit::event::WindowEvent::CursorLeft { .. } => self.cursor = None,
+                    winit::event::WindowEvent::MouseWheel { delta, .. } => self.mouse_wheel(delta),
+                    winit::event::WindowEvent::MouseInput { state, button, .. } => {
+                        self.mouse_input(state, button);
                     }
+                    _ => {}
                 }
             }
             _ => {}
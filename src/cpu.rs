@@ -0,0 +1,111 @@
+use crate::{grid::Grid, rule::Rule, simulator::Simulator, tiling::Tiling, topology::Topology};
+
+/// Pure Rust reference implementation, joins the edges like `compute.wgsl`.
+pub struct CpuSimulator {
+    pub grid: Grid,
+    pub rule: Rule,
+    pub topology: Topology,
+    pub generation: u64,
+    back: Grid,
+    /// `Rule::table` of an isotropic rule, looked up instead of counting neighbours.
+    table: Option<Box<[u32; 512]>>,
+}
+
+impl CpuSimulator {
+    pub fn new(grid: Grid, rule: Rule) -> Self {
+        let back = grid.clone();
+        Self {
+            grid,
+            table: rule.isotropic.map(|_| Box::new(rule.table())),
+            rule,
+            topology: Topology::default(),
+            generation: 0,
+            back,
+        }
+    }
+
+    /// State of the cell `offset` away from `cell`, 0 past a dead edge.
+    fn state_at(&self, cell: (u32, u32), offset: (i32, i32)) -> u32 {
+        let size = (self.grid.width, self.grid.height);
+        self.topology
+            .neighbour(cell, offset, size)
+            .map_or(0, |(x, y)| self.grid.get(x, y))
+    }
+
+    /// The 3x3 block around a cell, bit `i` set if the cell `i` in reading order is live.
+    fn block(&self, x: u32, y: u32) -> usize {
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+            .enumerate()
+            .map(|(i, offset)| ((self.state_at((x, y), offset) == 1) as usize) << i)
+            .sum()
+    }
+
+    fn active_neighbours(&self, x: u32, y: u32) -> u32 {
+        if self.rule.tiling != Tiling::Square {
+            return self
+                .rule
+                .tiling
+                .neighbours((x, y))
+                .map(|offset| (self.state_at((x, y), offset) == 1) as u32)
+                .sum();
+        }
+        let size = (self.grid.width, self.grid.height);
+        let (range, neighbourhood, middle) = self.rule.neighbourhood();
+        let range = range as i32;
+        let mut count = 0;
+        for dy in -range..=range {
+            let half_width = neighbourhood.half_width(range as u32, dy);
+            for dx in -half_width..=half_width {
+                if dx == 0 && dy == 0 && !middle {
+                    continue;
+                }
+                if let Some((nx, ny)) = self.topology.neighbour((x, y), (dx, dy), size) {
+                    count += (self.grid.get(nx, ny) == 1) as u32;
+                }
+            }
+        }
+        count
+    }
+}
+
+impl Simulator for CpuSimulator {
+    fn step(&mut self) {
+        for y in 0..self.grid.height {
+            for x in 0..self.grid.width {
+                let state = self.grid.get(x, y);
+                let v = match (&self.rule.tree, &self.table) {
+                    (Some(tree), _) => tree.next(|offset| self.state_at((x, y), offset)),
+                    (None, Some(table)) => self.rule.next_in_table(table, state, self.block(x, y)),
+                    (None, None) => self.rule.next(state, self.active_neighbours(x, y)),
+                };
+                self.back.set(x, y, v);
+            }
+        }
+        std::mem::swap(&mut self.grid, &mut self.back);
+        self.generation += 1;
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn read_cells(&mut self) -> Grid {
+        self.grid.clone()
+    }
+
+    fn write_cells(&mut self, grid: &Grid) {
+        self.grid = grid.clone();
+        // Steps write into `back`, it must have the size of the new grid.
+        self.back = grid.clone();
+    }
+
+    fn set_rule(&mut self, rule: Rule) {
+        self.table = rule.isotropic.map(|_| Box::new(rule.table()));
+        self.rule = rule;
+    }
+
+    fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+}
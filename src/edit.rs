@@ -0,0 +1,58 @@
+/// What a mouse button does to the cells it is dragged over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    /// Left button: paint live cells when dragged, toggle when clicked.
+    Draw,
+    /// Right button: erase cells.
+    Erase,
+}
+
+/// A button held down over the grid.
+pub struct Stroke {
+    pub tool: Tool,
+    /// Cell the button went down on.
+    pub start: (i64, i64),
+    /// Cell the cursor was last seen over.
+    pub last: (i64, i64),
+    /// Whether the cursor has left `start` yet.
+    pub moved: bool,
+}
+
+impl Stroke {
+    pub fn new(tool: Tool, cell: (i64, i64)) -> Self {
+        Self {
+            tool,
+            start: cell,
+            last: cell,
+            moved: false,
+        }
+    }
+}
+
+/// Cells on the straight line from `a` to `b`, both ends included, so fast drags leave no gaps.
+pub fn line(a: (i64, i64), b: (i64, i64)) -> Vec<(i64, i64)> {
+    let (mut x, mut y) = a;
+    let (x1, y1) = b;
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let sx = if x < x1 { 1 } else { -1 };
+    let sy = if y < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+
+    let mut cells = vec![];
+    loop {
+        cells.push((x, y));
+        if x == x1 && y == y1 {
+            return cells;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
@@ -0,0 +1,663 @@
+use std::sync::Arc;
+
+use wgpu::{
+    util::DeviceExt, Adapter, Backends, BindGroup, BindGroupDescriptor, BindGroupEntry,
+    BindGroupLayout, BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBindingType,
+    BufferUsages, ComputePipeline, Device, Features, Instance, PipelineLayout, Queue, ShaderModule,
+    ShaderModuleDescriptor, ShaderStages,
+};
+
+use crate::{grid::Grid, rule::Rule, simulator::Simulator, tiling::Tiling, topology::Topology};
+
+/// Long runs are split into command buffers of at most this many generations.
+const STEPS_PER_SUBMIT: u32 = 1024;
+
+/// Most cells one `cp_edit` dispatch changes, `set_cells` splits longer lists.
+const EDITS_PER_SUBMIT: usize = 256;
+
+/// Any adapter, falling back to a software one when there is no GPU.
+pub async fn request_headless_adapter(instance: &Instance) -> Option<Adapter> {
+    for force_fallback_adapter in [false, true] {
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptionsBase {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                force_fallback_adapter,
+                compatible_surface: None,
+            })
+            .await;
+        if adapter.is_some() {
+            return adapter;
+        }
+    }
+    None
+}
+
+/// How cells are stored in the GPU buffers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellLayout {
+    /// One `u32` per cell, stepped by `cp_main`.
+    Word,
+    /// 32 cells per `u32`, each row starting on a new word, stepped by `cp_packed`. Only for
+    /// Life-like rules.
+    Bit,
+}
+
+impl CellLayout {
+    /// The most compact layout `rule` can be stepped in.
+    pub fn for_rule(rule: &Rule) -> Self {
+        match rule.is_life_like() {
+            true => Self::Bit,
+            false => Self::Word,
+        }
+    }
+
+    /// Words in one row of a grid `width` cells wide.
+    pub fn row_len(self, width: u32) -> u32 {
+        match self {
+            Self::Word => width,
+            Self::Bit => width.div_ceil(32),
+        }
+    }
+
+    pub fn pack(self, grid: &Grid) -> Vec<u32> {
+        match self {
+            Self::Word => grid.cell_arr.clone(),
+            Self::Bit => {
+                let row_len = self.row_len(grid.width) as usize;
+                let mut words = vec![0; row_len * grid.height as usize];
+                for y in 0..grid.height {
+                    for x in 0..grid.width {
+                        if grid.get(x, y) != 0 {
+                            words[y as usize * row_len + x as usize / 32] |= 1 << (x % 32);
+                        }
+                    }
+                }
+                words
+            }
+        }
+    }
+
+    pub fn unpack(self, width: u32, height: u32, words: &[u32]) -> Grid {
+        let mut grid = Grid::empty(width, height);
+        match self {
+            Self::Word => grid.cell_arr.copy_from_slice(words),
+            Self::Bit => {
+                let row_len = self.row_len(width) as usize;
+                for y in 0..height {
+                    for x in 0..width {
+                        let word = words[y as usize * row_len + x as usize / 32];
+                        grid.set(x, y, word >> (x % 32) & 1);
+                    }
+                }
+            }
+        }
+        grid
+    }
+}
+
+/// The `compute.wgsl` entry point stepping `rule` in `layout`.
+fn entry_point(layout: CellLayout, rule: &Rule) -> &'static str {
+    match layout {
+        CellLayout::Bit => "cp_packed",
+        CellLayout::Word if rule.tree.is_some() => "cp_tree",
+        CellLayout::Word if rule.larger.is_some() => "cp_larger",
+        CellLayout::Word if rule.isotropic.is_some() => "cp_isotropic",
+        CellLayout::Word if rule.tiling != Tiling::Square => "cp_tiled",
+        CellLayout::Word => "cp_main",
+    }
+}
+
+/// Runs `compute.wgsl`, ping-ponging between two cell buffers in `layout`.
+pub struct GpuSimulator {
+    pub device: Arc<Device>,
+    pub queue: Arc<Queue>,
+
+    pub grid_size_buffer: Buffer,
+    pub rule_buffer: Buffer,
+    /// `Rule::table`, read by `cp_isotropic`.
+    pub table_buffer: Buffer,
+    /// Nodes of a `.rule` file's tree, read by `cp_tree`.
+    pub tree_buffer: Buffer,
+    pub topology_buffer: Buffer,
+    /// Edits `set_cells` hands to `cp_edit`.
+    pub edit_buffer: Buffer,
+    pub grid_cell_buffer_arr: [Buffer; 2],
+
+    pub compute_pipline: ComputePipeline,
+    pub edit_pipline: ComputePipeline,
+    pub compute_bind_group_arr: [BindGroup; 2],
+    /// Kept to switch entry points and tree buffers when the rule changes.
+    compute_bind_group_layout: BindGroupLayout,
+    compute_shader: ShaderModule,
+    compute_pipline_layout: PipelineLayout,
+
+    pub width: u32,
+    pub height: u32,
+    pub layout: CellLayout,
+    pub rule: Rule,
+    pub topology: Topology,
+
+    /// Index of the buffer holding the current generation.
+    pub step: u32,
+    pub generation: u64,
+}
+
+impl GpuSimulator {
+    pub fn new(device: Arc<Device>, queue: Arc<Queue>, grid: &Grid, rule: Rule) -> Self {
+        let layout = CellLayout::for_rule(&rule);
+        Self::with_layout(device, queue, grid, rule, layout)
+    }
+
+    pub fn with_layout(
+        device: Arc<Device>,
+        queue: Arc<Queue>,
+        grid: &Grid,
+        rule: Rule,
+        layout: CellLayout,
+    ) -> Self {
+        assert!(
+            layout == CellLayout::Word || rule.is_life_like(),
+            "{rule} cannot be stepped bit-packed"
+        );
+        let cells = layout.pack(grid);
+        let grid_size_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Grid Size Buffer"),
+            contents: bytemuck::cast_slice(&[grid.width as f32, grid.height as f32]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let rule_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Rule Buffer"),
+            contents: bytemuck::cast_slice(&rule.uniform()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let table_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Rule Table Buffer"),
+            contents: bytemuck::cast_slice(&rule.table()),
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        });
+        let tree_buffer = Self::create_tree_buffer(&device, &rule);
+        let topology_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Topology Buffer"),
+            contents: bytemuck::cast_slice(&Topology::default().uniform()),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let edit_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Cell Edit Buffer"),
+            size: ((1 + 3 * EDITS_PER_SUBMIT) * std::mem::size_of::<u32>()) as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let grid_cell_buffer_arr = Self::create_cell_buffers(&device, &cells);
+
+        let compute_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Compute Bind Group Layout"),
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::VERTEX | ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::VERTEX | ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 5,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 6,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 7,
+                        visibility: ShaderStages::COMPUTE,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let compute_pipline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Compute Pipline Layout"),
+                bind_group_layouts: &[&compute_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let compute_shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Compute Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../assets/shader/compute.wgsl").into()),
+        });
+
+        let compute_pipline = Self::create_compute_pipline(
+            &device,
+            &compute_pipline_layout,
+            &compute_shader,
+            entry_point(layout, &rule),
+        );
+        let edit_pipline = Self::create_compute_pipline(
+            &device,
+            &compute_pipline_layout,
+            &compute_shader,
+            "cp_edit",
+        );
+
+        let compute_bind_group_arr = Self::create_compute_bind_groups(
+            &device,
+            &compute_bind_group_layout,
+            &grid_size_buffer,
+            &grid_cell_buffer_arr,
+            &[
+                &rule_buffer,
+                &topology_buffer,
+                &table_buffer,
+                &tree_buffer,
+                &edit_buffer,
+            ],
+        );
+
+        Self {
+            device,
+            queue,
+            grid_size_buffer,
+            rule_buffer,
+            table_buffer,
+            tree_buffer,
+            topology_buffer,
+            edit_buffer,
+            grid_cell_buffer_arr,
+            compute_pipline,
+            edit_pipline,
+            compute_bind_group_arr,
+            compute_bind_group_layout,
+            compute_shader,
+            compute_pipline_layout,
+            width: grid.width,
+            height: grid.height,
+            layout,
+            rule,
+            topology: Topology::default(),
+            step: 0,
+            generation: 0,
+        }
+    }
+
+    /// Both cell buffers holding `cells`, packed in some layout.
+    fn create_cell_buffers(device: &Device, cells: &[u32]) -> [Buffer; 2] {
+        [(); 2].map(|_| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Grid Cell Buffer"),
+                contents: bytemuck::cast_slice(cells),
+                usage: BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+            })
+        })
+    }
+
+    /// One bind group per direction of stepping between the cell buffers, `rest` are bound
+    /// from binding 3 on.
+    fn create_compute_bind_groups(
+        device: &Device,
+        layout: &BindGroupLayout,
+        grid_size_buffer: &Buffer,
+        cells: &[Buffer; 2],
+        rest: &[&Buffer],
+    ) -> [BindGroup; 2] {
+        [("Compute Bind Group A", 0), ("Compute Bind Group B", 1)].map(|(label, from)| {
+            let buffers = [grid_size_buffer, &cells[from], &cells[1 - from]];
+            let entries: Vec<BindGroupEntry> = buffers
+                .into_iter()
+                .chain(rest.iter().copied())
+                .enumerate()
+                .map(|(binding, buffer)| BindGroupEntry {
+                    binding: binding as u32,
+                    resource: BindingResource::Buffer(buffer.as_entire_buffer_binding()),
+                })
+                .collect();
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some(label),
+                layout,
+                entries: &entries,
+            })
+        })
+    }
+
+    /// The nodes of `rule`'s tree for `cp_tree`, a placeholder word for other rules.
+    fn create_tree_buffer(device: &Device, rule: &Rule) -> Buffer {
+        let nodes = rule.tree.as_ref().map_or(&[0][..], |tree| &tree.nodes);
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Rule Tree Buffer"),
+            contents: bytemuck::cast_slice(nodes),
+            usage: BufferUsages::STORAGE,
+        })
+    }
+
+    fn create_compute_pipline(
+        device: &Device,
+        layout: &PipelineLayout,
+        shader: &ShaderModule,
+        entry_point: &str,
+    ) -> ComputePipeline {
+        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Compute Pipline"),
+            layout: Some(layout),
+            module: shader,
+            entry_point,
+        })
+    }
+
+    /// Creates its own device on any adapter, no surface needed.
+    pub async fn headless(grid: &Grid, rule: Rule) -> Option<Self> {
+        let layout = CellLayout::for_rule(&rule);
+        Self::headless_with_layout(grid, rule, layout).await
+    }
+
+    pub async fn headless_with_layout(grid: &Grid, rule: Rule, layout: CellLayout) -> Option<Self> {
+        let instance = Instance::new(wgpu::InstanceDescriptor {
+            backends: Backends::all(),
+            dx12_shader_compiler: Default::default(),
+        });
+
+        let adapter = request_headless_adapter(&instance).await?;
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("Device"),
+                    features: Features::empty(),
+                    limits: adapter.limits(),
+                },
+                None,
+            )
+            .await
+            .ok()?;
+
+        Some(Self::with_layout(
+            Arc::new(device),
+            Arc::new(queue),
+            grid,
+            rule,
+            layout,
+        ))
+    }
+
+    pub fn current_buffer(&self) -> &Buffer {
+        &self.grid_cell_buffer_arr[self.step as usize]
+    }
+
+    /// Copy the current generation into a staging buffer and map it back.
+    pub async fn read(&self) -> Grid {
+        let mut words = vec![0; (self.layout.row_len(self.width) * self.height) as usize];
+        self.read_into(0, &mut words).await;
+        self.layout.unpack(self.width, self.height, &words)
+    }
+
+    /// State of a single cell of the current generation.
+    pub async fn get_cell(&self, x: u32, y: u32) -> u32 {
+        let mut word = [0];
+        self.read_into(self.word_offset(x, y), &mut word).await;
+        match self.layout {
+            CellLayout::Word => word[0],
+            CellLayout::Bit => word[0] >> (x % 32) & 1,
+        }
+    }
+
+    pub fn set_cell(&self, x: u32, y: u32, v: u32) {
+        self.set_cells(&[(x, y, v)]);
+    }
+
+    /// Set each `(x, y, state)` in order. Bit-packed words are changed on the GPU by `cp_edit`,
+    /// so nothing is read back however many cells there are.
+    pub fn set_cells(&self, cells: &[(u32, u32, u32)]) {
+        for chunk in cells.chunks(EDITS_PER_SUBMIT) {
+            let mut edits = vec![chunk.len() as u32];
+            for &(x, y, v) in chunk {
+                let (mask, value) = match self.layout {
+                    CellLayout::Word => (!0, v),
+                    CellLayout::Bit => (1 << (x % 32), (v & 1) << (x % 32)),
+                };
+                edits.extend([self.word_index(x, y) as u32, mask, value]);
+            }
+            self.queue
+                .write_buffer(&self.edit_buffer, 0, bytemuck::cast_slice(&edits));
+
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            {
+                let mut compute_pass =
+                    encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+                compute_pass.set_pipeline(&self.edit_pipline);
+                // The bind group stepping into the current buffer writes to it.
+                let bind_group = &self.compute_bind_group_arr[1 - self.step as usize];
+                compute_pass.set_bind_group(0, bind_group, &[]);
+                compute_pass.dispatch_workgroups(1, 1, 1);
+            }
+            self.queue.submit(std::iter::once(encoder.finish()));
+        }
+    }
+
+    /// Index of the word holding the cell at `(x, y)`.
+    fn word_index(&self, x: u32, y: u32) -> u64 {
+        let row_len = self.layout.row_len(self.width) as u64;
+        let column = match self.layout {
+            CellLayout::Word => x,
+            CellLayout::Bit => x / 32,
+        };
+        y as u64 * row_len + column as u64
+    }
+
+    /// Byte offset of the word holding the cell at `(x, y)`.
+    fn word_offset(&self, x: u32, y: u32) -> u64 {
+        self.word_index(x, y) * std::mem::size_of::<u32>() as u64
+    }
+
+    async fn read_into(&self, offset: u64, cells: &mut [u32]) {
+        let size = std::mem::size_of_val(cells) as u64;
+        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Grid Cell Staging Buffer"),
+            size,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_buffer_to_buffer(self.current_buffer(), offset, &staging_buffer, 0, size);
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = staging_buffer.slice(..);
+        let (tx, rx) = futures_channel::oneshot::channel();
+        slice.map_async(wgpu::MapMode::Read, move |res| {
+            let _ = tx.send(res);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        rx.await.unwrap().unwrap();
+
+        cells.copy_from_slice(bytemuck::cast_slice(&slice.get_mapped_range()));
+        staging_buffer.unmap();
+    }
+
+    /// Rebuild the compute bind groups after the cell or tree buffers changed.
+    fn bind_cells(&mut self) {
+        self.compute_bind_group_arr = Self::create_compute_bind_groups(
+            &self.device,
+            &self.compute_bind_group_layout,
+            &self.grid_size_buffer,
+            &self.grid_cell_buffer_arr,
+            &[
+                &self.rule_buffer,
+                &self.topology_buffer,
+                &self.table_buffer,
+                &self.tree_buffer,
+                &self.edit_buffer,
+            ],
+        );
+    }
+
+    fn encode_step(&self, encoder: &mut wgpu::CommandEncoder, step: u32) {
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+        compute_pass.set_pipeline(&self.compute_pipline);
+        compute_pass.set_bind_group(0, &self.compute_bind_group_arr[step as usize], &[]);
+        compute_pass.dispatch_workgroups(
+            (self.layout.row_len(self.width) as f32 / 8.0).ceil() as _,
+            (self.height as f32 / 8.0).ceil() as _,
+            1,
+        );
+    }
+}
+
+impl Simulator for GpuSimulator {
+    fn step(&mut self) {
+        self.step_n(1);
+    }
+
+    fn step_n(&mut self, n: u32) {
+        let mut left = n;
+        while left > 0 {
+            let batch = left.min(STEPS_PER_SUBMIT);
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+            for _ in 0..batch {
+                self.encode_step(&mut encoder, self.step);
+                self.step = 1 - self.step;
+            }
+            self.queue.submit(std::iter::once(encoder.finish()));
+            left -= batch;
+        }
+        self.generation += n as u64;
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn read_cells(&mut self) -> Grid {
+        pollster::block_on(self.read())
+    }
+
+    /// A grid of another size gets new buffers, anything bound to `grid_cell_buffer_arr` has to
+    /// be bound again then, as after `set_rule`.
+    fn write_cells(&mut self, grid: &Grid) {
+        if (grid.width, grid.height) == (self.width, self.height) {
+            self.queue.write_buffer(
+                self.current_buffer(),
+                0,
+                bytemuck::cast_slice(&self.layout.pack(grid)),
+            );
+            return;
+        }
+        self.width = grid.width;
+        self.height = grid.height;
+        self.queue.write_buffer(
+            &self.grid_size_buffer,
+            0,
+            bytemuck::cast_slice(&[grid.width as f32, grid.height as f32]),
+        );
+        self.grid_cell_buffer_arr =
+            Self::create_cell_buffers(&self.device, &self.layout.pack(grid));
+        self.bind_cells();
+    }
+
+    /// Moves the cells into new buffers when `rule` wants another `CellLayout`, anything
+    /// bound to `grid_cell_buffer_arr` has to be bound again then.
+    fn set_rule(&mut self, rule: Rule) {
+        let old_layout = self.layout;
+        let layout = CellLayout::for_rule(&rule);
+        if layout != old_layout {
+            let grid = pollster::block_on(self.read());
+            self.grid_cell_buffer_arr =
+                Self::create_cell_buffers(&self.device, &layout.pack(&grid));
+            self.layout = layout;
+        }
+        let entry = entry_point(layout, &rule);
+        if entry != entry_point(old_layout, &self.rule) {
+            self.compute_pipline = Self::create_compute_pipline(
+                &self.device,
+                &self.compute_pipline_layout,
+                &self.compute_shader,
+                entry,
+            );
+        }
+        if rule.tree != self.rule.tree {
+            self.tree_buffer = Self::create_tree_buffer(&self.device, &rule);
+        }
+        if rule.tree != self.rule.tree || layout != old_layout {
+            self.bind_cells();
+        }
+        self.queue
+            .write_buffer(&self.rule_buffer, 0, bytemuck::cast_slice(&rule.uniform()));
+        self.queue
+            .write_buffer(&self.table_buffer, 0, bytemuck::cast_slice(&rule.table()));
+        self.rule = rule;
+    }
+
+    fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+        self.queue.write_buffer(
+            &self.topology_buffer,
+            0,
+            bytemuck::cast_slice(&topology.uniform()),
+        );
+    }
+}
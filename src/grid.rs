@@ -1,22 +1,53 @@
-use rand::Rng;
-
-pub const GRID_SIZE: u32 = 40;
-pub const GRID_PIXEL_SIZE: u32 = 800;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
+#[derive(Clone)]
 pub struct Grid {
-    pub cell_arr: [u32; (GRID_SIZE * GRID_SIZE) as _],
+    pub width: u32,
+    pub height: u32,
+    pub cell_arr: Vec<u32>,
     pub step: u32,
 }
 
 impl Grid {
-    pub fn new() -> Self {
-        let mut rng = rand::thread_rng();
-        let mut cell_arr = [0; (GRID_SIZE * GRID_SIZE) as _];
-        for i in 0..(GRID_SIZE * GRID_SIZE) {
-            let r = rng.gen::<f32>() > 0.7;
-            let v = if r { 1 } else { 0 };
-            cell_arr[i as usize] = v;
+    /// Each cell live with probability `density`, the same `seed` always gives the same grid.
+    /// ChaCha8 is used since `StdRng` may change its algorithm between rand releases.
+    pub fn random(width: u32, height: u32, density: f64, seed: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut grid = Self::empty(width, height);
+        for v in grid.cell_arr.iter_mut() {
+            *v = rng.gen_bool(density.clamp(0.0, 1.0)) as u32;
+        }
+        grid
+    }
+
+    pub fn empty(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            cell_arr: vec![0; width as usize * height as usize],
+            step: 0,
         }
-        Self { cell_arr, step: 0 }
+    }
+
+    pub fn len(&self) -> u32 {
+        self.width * self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> u32 {
+        self.cell_arr[self.index(x, y)]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, v: u32) {
+        let i = self.index(x, y);
+        self.cell_arr[i] = v;
     }
 }
@@ -0,0 +1,546 @@
+//! Gosper's HashLife: a canonical quadtree whose nodes memoise their own future, so repetitive
+//! patterns can be advanced by huge powers of two at once.
+
+use std::collections::HashMap;
+
+use crate::{
+    grid::Grid,
+    pattern::macrocell::{Macrocell, Node as MacrocellNode},
+    rule::Rule,
+    simulator::UnboundedSimulator,
+};
+
+/// Index into `HashLife::nodes`. 0 and 1 are the dead and live cells.
+pub type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+/// Highest root level, the root's side and every cell's coordinates fit an `i64`.
+const MAX_LEVEL: u32 = 62;
+
+/// Largest step, `2^MAX_STEP_LOG2` generations, the root is at least two levels above it.
+pub const MAX_STEP_LOG2: u32 = MAX_LEVEL - 2;
+
+/// A `2^level` square, made of four `2^(level - 1)` children unless it is a single cell.
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    level: u32,
+    /// nw, ne, sw, se.
+    children: [NodeId; 4],
+    population: u64,
+}
+
+/// Life on an unbounded plane, stepped by HashLife. Like `SparseLife`, rules with B0 are not
+/// supported.
+pub struct HashLife {
+    pub rule: Rule,
+    pub generation: u64,
+    /// `collect_garbage` runs before a step once the node cache is this large.
+    pub gc_threshold: usize,
+    nodes: Vec<Node>,
+    index: HashMap<[NodeId; 4], NodeId>,
+    /// Keyed by node and step size, see `result`.
+    results: HashMap<(NodeId, u32), NodeId>,
+    /// Empty node of each level, built on demand.
+    empty: Vec<NodeId>,
+    /// Always centred on the origin, covering `-2^(level - 1)..2^(level - 1)` on both axes.
+    root: NodeId,
+    /// Each `step` advances `2^step_log2` generations.
+    step_log2: u32,
+}
+
+impl HashLife {
+    pub const DEFAULT_GC_THRESHOLD: usize = 1 << 22;
+
+    /// Macrocell node number of `id`, 0 if it is empty.
+    fn write_macrocell(
+        &self,
+        id: NodeId,
+        nodes: &mut Vec<MacrocellNode>,
+        numbers: &mut HashMap<NodeId, usize>,
+    ) -> usize {
+        if self.nodes[id as usize].population == 0 {
+            return 0;
+        }
+        if let Some(&number) = numbers.get(&id) {
+            return number;
+        }
+        let node = self.nodes[id as usize];
+        let written = if node.level == 3 {
+            let mut rows = [0u8; 8];
+            for (y, row) in rows.iter_mut().enumerate() {
+                for x in 0..8 {
+                    if self.cell_in(id, x, y as i64) {
+                        *row |= 1 << x;
+                    }
+                }
+            }
+            MacrocellNode::Leaf(rows)
+        } else {
+            MacrocellNode::Inner {
+                level: node.level,
+                children: node
+                    .children
+                    .map(|child| self.write_macrocell(child, nodes, numbers)),
+            }
+        };
+        nodes.push(written);
+        numbers.insert(id, nodes.len());
+        nodes.len()
+    }
+
+    fn level(&self, id: NodeId) -> u32 {
+        self.nodes[id as usize].level
+    }
+
+    fn children(&self, id: NodeId) -> [NodeId; 4] {
+        self.nodes[id as usize].children
+    }
+
+    /// The canonical node with these children, all of one level.
+    fn join(&mut self, children: [NodeId; 4]) -> NodeId {
+        if let Some(&id) = self.index.get(&children) {
+            return id;
+        }
+        let level = self.level(children[0]) + 1;
+        let population = children
+            .iter()
+            .map(|&child| self.nodes[child as usize].population)
+            .sum();
+        self.nodes.push(Node {
+            level,
+            children,
+            population,
+        });
+        let id = (self.nodes.len() - 1) as NodeId;
+        self.index.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u32) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let below = *self.empty.last().unwrap();
+            let id = self.join([below; 4]);
+            self.empty.push(id);
+        }
+        self.empty[level as usize]
+    }
+
+    /// A `2^level` square with cells from `cell(x, y)`, `x` and `y` from its top left corner.
+    fn build_square(&mut self, level: u32, cell: &dyn Fn(usize, usize) -> NodeId) -> NodeId {
+        if level == 0 {
+            return cell(0, 0);
+        }
+        let half = 1 << (level - 1);
+        let children = [(0, 0), (half, 0), (0, half), (half, half)]
+            .map(|(dx, dy)| self.build_square(level - 1, &|x, y| cell(x + dx, y + dy)));
+        self.join(children)
+    }
+
+    /// The same square one level up, padded with dead cells all round.
+    fn expand(&mut self, id: NodeId) -> NodeId {
+        let level = self.level(id);
+        let empty = self.empty(level - 1);
+        let [nw, ne, sw, se] = self.children(id);
+        let nw = self.join([empty, empty, empty, nw]);
+        let ne = self.join([empty, empty, ne, empty]);
+        let sw = self.join([empty, sw, empty, empty]);
+        let se = self.join([se, empty, empty, empty]);
+        self.join([nw, ne, sw, se])
+    }
+
+    /// The middle half of a node, one level down.
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let [nw, ne, sw, se] = self.children(id).map(|child| self.children(child));
+        self.join([nw[3], ne[2], sw[1], se[0]])
+    }
+
+    fn centre_horizontal(&mut self, w: NodeId, e: NodeId) -> NodeId {
+        let (w, e) = (self.children(w), self.children(e));
+        self.join([w[1], e[0], w[3], e[2]])
+    }
+
+    fn centre_vertical(&mut self, n: NodeId, s: NodeId) -> NodeId {
+        let (n, s) = (self.children(n), self.children(s));
+        self.join([n[2], n[3], s[0], s[1]])
+    }
+
+    /// The centre half of `id`, `2^min(step_log2, level - 2)` generations later.
+    fn result(&mut self, id: NodeId) -> NodeId {
+        let node = self.nodes[id as usize];
+        let step_log2 = self.step_log2.min(node.level - 2);
+        if node.population == 0 {
+            return self.empty(node.level - 1);
+        }
+        if let Some(&result) = self.results.get(&(id, step_log2)) {
+            return result;
+        }
+
+        let result = if node.level == 2 {
+            self.result_4x4(id)
+        } else {
+            let [nw, ne, sw, se] = node.children;
+            let n01 = self.centre_horizontal(nw, ne);
+            let n10 = self.centre_vertical(nw, sw);
+            let n11 = self.centre(id);
+            let n12 = self.centre_vertical(ne, se);
+            let n21 = self.centre_horizontal(sw, se);
+            let nine = [nw, n01, ne, n10, n11, n12, sw, n21, se];
+
+            // At full speed both halves of the step advance, otherwise only the second.
+            let full_speed = step_log2 == node.level - 2;
+            let r = nine.map(|n| {
+                if full_speed {
+                    self.result(n)
+                } else {
+                    self.centre(n)
+                }
+            });
+            let quarters = [
+                [r[0], r[1], r[3], r[4]],
+                [r[1], r[2], r[4], r[5]],
+                [r[3], r[4], r[6], r[7]],
+                [r[4], r[5], r[7], r[8]],
+            ]
+            .map(|children| {
+                let quarter = self.join(children);
+                self.result(quarter)
+            });
+            self.join(quarters)
+        };
+        self.results.insert((id, step_log2), result);
+        result
+    }
+
+    /// One generation of the middle 2x2 of a 4x4 node, cell by cell.
+    fn result_4x4(&mut self, id: NodeId) -> NodeId {
+        let cell = |x: i64, y: i64| self.cell_in(id, x, y) as u32;
+        let next = |x: i64, y: i64| {
+            let mut count = 0;
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if dx != 0 || dy != 0 {
+                        count += cell(x + dx, y + dy);
+                    }
+                }
+            }
+            self.rule.next(cell(x, y), count) as NodeId
+        };
+        let children = [next(1, 1), next(2, 1), next(1, 2), next(2, 2)];
+        self.join(children)
+    }
+
+    /// Whether `(x, y)`, from the top left corner of `id`, is alive.
+    fn cell_in(&self, mut id: NodeId, mut x: i64, mut y: i64) -> bool {
+        loop {
+            let node = self.nodes[id as usize];
+            if node.level == 0 {
+                return id == ALIVE;
+            }
+            if node.population == 0 {
+                return false;
+            }
+            let half = 1 << (node.level - 1);
+            let quadrant = (x >= half) as usize + 2 * (y >= half) as usize;
+            id = node.children[quadrant];
+            x %= half;
+            y %= half;
+        }
+    }
+
+    /// `id` with `(x, y)`, from its top left corner, set to `cell`.
+    fn set_in(&mut self, id: NodeId, x: i64, y: i64, cell: NodeId) -> NodeId {
+        let level = self.level(id);
+        if level == 0 {
+            return cell;
+        }
+        let half = 1 << (level - 1);
+        let quadrant = (x >= half) as usize + 2 * (y >= half) as usize;
+        let mut children = self.children(id);
+        children[quadrant] = self.set_in(children[quadrant], x % half, y % half, cell);
+        self.join(children)
+    }
+
+    /// Half the root's side, the root covers `-half..half` on both axes.
+    fn half(&self) -> i64 {
+        1 << (self.level(self.root) - 1)
+    }
+
+    /// Generations advanced by each `step`, a power of two.
+    pub fn step_size(&self) -> u64 {
+        1 << self.step_log2
+    }
+
+    /// Make each `step` advance `2^step_log2` generations. Results for other step sizes stay
+    /// cached, so switching back and forth is cheap.
+    pub fn set_step_log2(&mut self, step_log2: u32) {
+        assert!(step_log2 <= MAX_STEP_LOG2, "step size out of range");
+        self.step_log2 = step_log2;
+    }
+
+    /// Advance `step_size()` generations. `false`, with nothing changed, if the pattern would
+    /// spread past `MAX_LEVEL`.
+    pub fn step(&mut self) -> bool {
+        if self.nodes.len() > self.gc_threshold {
+            self.collect_garbage();
+        }
+        // Nothing may leave the root's centre half during the step. With the pattern inside the
+        // centre half and the root two levels above the step, one more ring of padding puts it in
+        // the centre quarter, which it cannot get out of in time.
+        let root = self.root;
+        loop {
+            let level = self.level(self.root);
+            let [nw, ne, sw, se] = self.children(self.root).map(|child| self.children(child));
+            let inner = [nw[3], ne[2], sw[1], se[0]]
+                .iter()
+                .map(|&id| self.nodes[id as usize].population)
+                .sum::<u64>();
+            if level >= self.step_log2 + 2 && inner == self.population() {
+                break;
+            }
+            if level == MAX_LEVEL {
+                self.root = root;
+                return false;
+            }
+            self.root = self.expand(self.root);
+        }
+        self.root = self.expand(self.root);
+        self.root = self.result(self.root);
+        self.generation += self.step_size();
+        true
+    }
+
+    /// Nodes in the cache, live or not.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Drop every node the root no longer uses, along with all memoised results.
+    pub fn collect_garbage(&mut self) {
+        let mut remap: HashMap<NodeId, NodeId> = HashMap::new();
+        let cells = self.nodes[..2].to_vec();
+        let old = std::mem::replace(&mut self.nodes, cells);
+        self.index.clear();
+        self.results.clear();
+        self.empty.truncate(1);
+        remap.insert(DEAD, DEAD);
+        remap.insert(ALIVE, ALIVE);
+
+        // Children always come before their parents, so one pass in order keeps them valid.
+        let mut keep = vec![false; old.len()];
+        keep[self.root as usize] = true;
+        for id in (2..old.len()).rev() {
+            if keep[id] {
+                for child in old[id].children {
+                    keep[child as usize] = true;
+                }
+            }
+        }
+        for id in 2..old.len() {
+            if keep[id] {
+                let children = old[id].children.map(|child| remap[&child]);
+                let new = self.join(children);
+                remap.insert(id as NodeId, new);
+            }
+        }
+        self.root = remap[&self.root];
+    }
+
+    fn bounds_in(&self, id: NodeId, x: i64, y: i64, bounds: &mut ((i64, i64), (i64, i64))) {
+        let node = self.nodes[id as usize];
+        if node.population == 0 {
+            return;
+        }
+        let size = 1i64 << node.level;
+        let ((min_x, min_y), (max_x, max_y)) = *bounds;
+        // Nothing in here can widen the box.
+        if x >= min_x && y >= min_y && x + size - 1 <= max_x && y + size - 1 <= max_y {
+            return;
+        }
+        if node.level == 0 {
+            *bounds = ((min_x.min(x), min_y.min(y)), (max_x.max(x), max_y.max(y)));
+            return;
+        }
+        let half = size / 2;
+        for (i, &child) in node.children.iter().enumerate() {
+            let (dx, dy) = ((i % 2) as i64 * half, (i / 2) as i64 * half);
+            self.bounds_in(child, x + dx, y + dy, bounds);
+        }
+    }
+
+    /// Every live cell, in no particular order.
+    pub fn live_cells(&self) -> Vec<(i64, i64)> {
+        let mut cells = vec![];
+        let half = self.half();
+        self.cells_in(self.root, -half, -half, &mut cells);
+        cells
+    }
+
+    fn cells_in(&self, id: NodeId, x: i64, y: i64, cells: &mut Vec<(i64, i64)>) {
+        let node = self.nodes[id as usize];
+        if node.population == 0 {
+            return;
+        }
+        if node.level == 0 {
+            cells.push((x, y));
+            return;
+        }
+        let half = 1i64 << (node.level - 1);
+        for (i, &child) in node.children.iter().enumerate() {
+            let (dx, dy) = ((i % 2) as i64 * half, (i / 2) as i64 * half);
+            self.cells_in(child, x + dx, y + dy, cells);
+        }
+    }
+}
+
+impl UnboundedSimulator for HashLife {
+    fn new(rule: Rule) -> Self {
+        assert!(
+            rule.birth & 1 == 0,
+            "B0 rules cannot run on an infinite plane"
+        );
+        assert!(
+            rule.is_life_like(),
+            "{rule} cannot run on an infinite plane"
+        );
+        let cell = |population| Node {
+            level: 0,
+            children: [DEAD; 4],
+            population,
+        };
+        let mut life = Self {
+            rule,
+            generation: 0,
+            gc_threshold: Self::DEFAULT_GC_THRESHOLD,
+            nodes: vec![cell(0), cell(1)],
+            index: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            root: DEAD,
+            step_log2: 0,
+        };
+        life.root = life.empty(3);
+        life
+    }
+
+    /// Loads the tree as is.
+    fn from_macrocell(macrocell: &Macrocell, rule: Rule) -> Self {
+        let mut life = Self::new(rule);
+        let mut ids = vec![DEAD; macrocell.nodes.len() + 1];
+        for (i, node) in macrocell.nodes.iter().enumerate() {
+            ids[i + 1] = match *node {
+                MacrocellNode::Leaf(rows) => {
+                    let cells: Vec<NodeId> = (0..64)
+                        .map(|i| (rows[i / 8] >> (i % 8) & 1) as NodeId)
+                        .collect();
+                    life.build_square(3, &|x, y| cells[y * 8 + x])
+                }
+                MacrocellNode::Cells(states) => {
+                    let [nw, ne, sw, se] = states.map(|state| (state != 0) as NodeId);
+                    life.join([nw, ne, sw, se])
+                }
+                MacrocellNode::Inner { level, children } => {
+                    let children = children.map(|child| match child {
+                        0 => life.empty(level - 1),
+                        child => ids[child],
+                    });
+                    life.join(children)
+                }
+            };
+        }
+        life.root = *ids.last().unwrap();
+        while life.level(life.root) < 3 {
+            life.root = life.expand(life.root);
+        }
+        life.generation = macrocell.generation.unwrap_or(0);
+        life
+    }
+
+    /// The whole tree, root and all.
+    fn to_macrocell(&self) -> Macrocell {
+        let mut macrocell = Macrocell {
+            nodes: vec![],
+            rule: Some(self.rule),
+            bounds: None,
+            generation: Some(self.generation),
+            comments: vec![],
+        };
+        let mut numbers = HashMap::new();
+        if self.write_macrocell(self.root, &mut macrocell.nodes, &mut numbers) == 0 {
+            macrocell.nodes.push(MacrocellNode::Inner {
+                level: self.level(self.root),
+                children: [0; 4],
+            });
+        }
+        macrocell
+    }
+
+    fn get(&self, x: i64, y: i64) -> bool {
+        let half = self.half();
+        if x < -half || y < -half || x >= half || y >= half {
+            return false;
+        }
+        self.cell_in(self.root, x + half, y + half)
+    }
+
+    fn set(&mut self, x: i64, y: i64, alive: bool) {
+        while x < -self.half() || y < -self.half() || x >= self.half() || y >= self.half() {
+            self.root = self.expand(self.root);
+        }
+        let half = self.half();
+        self.root = self.set_in(self.root, x + half, y + half, alive as NodeId);
+    }
+
+    fn population(&self) -> u64 {
+        self.nodes[self.root as usize].population
+    }
+
+    fn bounding_box(&self) -> Option<((i64, i64), (i64, i64))> {
+        if self.population() == 0 {
+            return None;
+        }
+        let half = self.half();
+        let mut bounds = ((i64::MAX, i64::MAX), (i64::MIN, i64::MIN));
+        self.bounds_in(self.root, -half, -half, &mut bounds);
+        Some(bounds)
+    }
+
+    fn to_grid(&self) -> Option<(Grid, (i64, i64))> {
+        let ((min_x, min_y), (max_x, max_y)) = self.bounding_box()?;
+        let mut grid = Grid::empty((max_x - min_x + 1) as u32, (max_y - min_y + 1) as u32);
+        for (x, y) in self.live_cells() {
+            grid.set((x - min_x) as u32, (y - min_y) as u32, 1);
+        }
+        Some((grid, (min_x, min_y)))
+    }
+
+    /// Advance exactly `n` generations, one power of two at a time. Powers above
+    /// `2^MAX_STEP_LOG2` are made of steps that size. Stops early at generation `u64::MAX` or
+    /// once the pattern would spread past `MAX_LEVEL`. The step size is left as it was.
+    fn step_n(&mut self, n: u64) {
+        let n = n.min(u64::MAX - self.generation);
+        let step_log2 = self.step_log2;
+        let steps = std::iter::repeat_n(MAX_STEP_LOG2, (n >> MAX_STEP_LOG2) as usize)
+            .chain((0..MAX_STEP_LOG2).rev().filter(|bit| n >> bit & 1 == 1));
+        for bit in steps {
+            self.set_step_log2(bit);
+            if !self.step() {
+                log::warn!(
+                    "stopped at generation {}, the pattern is too large",
+                    self.generation
+                );
+                break;
+            }
+        }
+        self.step_log2 = step_log2;
+    }
+
+    fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+}
@@ -1,5 +1,20 @@
+pub mod camera;
+pub mod cli;
 pub mod core;
+pub mod cpu;
+pub mod edit;
+pub mod gpu;
 pub mod grid;
+pub mod hashlife;
+pub mod packed;
+pub mod pattern;
+pub mod rule;
 pub mod runner;
+pub mod scheduler;
+pub mod simulator;
+pub mod soup;
+pub mod sparse;
+pub mod tiling;
+pub mod topology;
 pub mod transform;
 pub mod vertex;
@@ -1,5 +1,10 @@
-use game_of_life::runner;
+use clap::Parser;
+use game_of_life::{cli::Args, runner};
 
 fn main() {
-    runner::run();
+    env_logger::init();
+    if let Err(e) = runner::run(Args::parse()) {
+        eprintln!("error: {e}");
+        std::process::exit(1);
+    }
 }
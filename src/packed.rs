@@ -0,0 +1,249 @@
+use std::{num::NonZeroUsize, thread};
+
+use crate::{
+    grid::Grid,
+    rule::Rule,
+    simulator::Simulator,
+    topology::{Edge, Topology},
+};
+
+/// Cells per word.
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Bands thinner than this are not worth a thread of their own.
+const MIN_BAND_ROWS: usize = 32;
+
+/// Multithreaded bit-parallel CPU backend for throughput: 64 cells per `u64` stepped with
+/// bitwise adders on plain words, no SIMD, and joined at the edges like `compute.wgsl`. Only
+/// for Life-like rules. Each step splits the rows into bands, one per thread, that only see
+/// copies of the rows and edge cells just outside them.
+pub struct PackedSimulator {
+    pub rule: Rule,
+    pub topology: Topology,
+    pub generation: u64,
+    /// Most bands a step is split into, one per available CPU by default.
+    pub threads: usize,
+    width: usize,
+    height: usize,
+    /// Words per row, each row starts on a new word and bits past the width are always 0.
+    row_len: usize,
+    cells: Vec<u64>,
+    back: Vec<u64>,
+}
+
+/// What one band needs from outside itself, copied before the bands run.
+struct Halo {
+    above: Vec<u64>,
+    below: Vec<u64>,
+    /// Per row of the band, bit `dy + 1` is the cell left of the row `dy` away and bit
+    /// `dy + 4` the cell right of it.
+    edges: Vec<u8>,
+}
+
+impl PackedSimulator {
+    pub fn new(grid: Grid, rule: Rule) -> Self {
+        assert!(rule.is_life_like(), "{rule} is not Life-like");
+        let mut simulator = Self {
+            rule,
+            topology: Topology::default(),
+            generation: 0,
+            threads: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            width: 0,
+            height: 0,
+            row_len: 0,
+            cells: Vec::new(),
+            back: Vec::new(),
+        };
+        simulator.write_cells(&grid);
+        simulator
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.cells[y * self.row_len + x / WORD_BITS] >> (x % WORD_BITS) & 1 == 1
+    }
+
+    fn row(&self, y: usize) -> &[u64] {
+        &self.cells[y * self.row_len..(y + 1) * self.row_len]
+    }
+
+    /// Cells of row `y` as seen from inside the grid, `y` may be one past either edge.
+    fn halo_row(&self, y: isize) -> Vec<u64> {
+        let h = self.height as isize;
+        if (0..h).contains(&y) {
+            return self.row(y as usize).to_vec();
+        }
+        let wrapped = y.rem_euclid(h) as usize;
+        match self.topology.y {
+            Edge::Dead => vec![0; self.row_len],
+            Edge::Wrap => self.row(wrapped).to_vec(),
+            Edge::Twist => {
+                let mut row = vec![0; self.row_len];
+                for x in (0..self.width).filter(|&x| self.get(x, wrapped)) {
+                    let x = self.width - 1 - x;
+                    row[x / WORD_BITS] |= 1 << (x % WORD_BITS);
+                }
+                row
+            }
+        }
+    }
+
+    /// The cells just left and right of rows `y - 1..=y + 1`, packed as in `Halo::edges`.
+    fn edge_bits(&self, y: usize) -> u8 {
+        let size = (self.width as u32, self.height as u32);
+        let (last, y) = (self.width as u32 - 1, y as u32);
+        let mut bits = 0;
+        for dy in -1..=1 {
+            let cells = [((0, y), (-1, dy)), ((last, y), (1, dy))];
+            for (side, (cell, offset)) in cells.into_iter().enumerate() {
+                if let Some((x, y)) = self.topology.neighbour(cell, offset, size) {
+                    bits |=
+                        (self.get(x as usize, y as usize) as u8) << (side * 3 + (dy + 1) as usize);
+                }
+            }
+        }
+        bits
+    }
+}
+
+/// Next generation of the rows in `band`, written to `out`.
+fn step_band(rule: Rule, width: usize, band: &[u64], halo: &Halo, out: &mut [u64]) {
+    let row_len = out.len() / halo.edges.len();
+    let rows = |r: isize| match r {
+        -1 => &halo.above[..],
+        r if r as usize == halo.edges.len() => &halo.below[..],
+        r => &band[r as usize * row_len..(r as usize + 1) * row_len],
+    };
+    // All ones where `n` live neighbours give birth or survival.
+    let born: [u64; 9] = std::array::from_fn(|n| 0u64.wrapping_sub((rule.birth >> n & 1) as u64));
+    let survive: [u64; 9] =
+        std::array::from_fn(|n| 0u64.wrapping_sub((rule.survival >> n & 1) as u64));
+    let valid = match width % WORD_BITS {
+        0 => !0,
+        bits => (1 << bits) - 1,
+    };
+
+    let mut west = vec![[0u64; 3]; row_len];
+    let mut east = vec![[0u64; 3]; row_len];
+    for (r, (next, &edges)) in out.chunks_mut(row_len).zip(&halo.edges).enumerate() {
+        for dy in 0..3 {
+            let row = rows(r as isize + dy as isize - 1);
+            let mut carry = (edges >> dy & 1) as u64;
+            for (west, &word) in west.iter_mut().zip(row) {
+                west[dy] = word << 1 | carry;
+                carry = word >> (WORD_BITS - 1);
+            }
+            let mut carry = 0;
+            for (east, &word) in east.iter_mut().zip(row).rev() {
+                east[dy] = word >> 1 | carry << (WORD_BITS - 1);
+                carry = word & 1;
+            }
+            east[row_len - 1][dy] |= ((edges >> (dy + 3) & 1) as u64) << ((width - 1) % WORD_BITS);
+        }
+
+        let (above, centre, below) = (rows(r as isize - 1), rows(r as isize), rows(r as isize + 1));
+        for i in 0..row_len {
+            let neighbours = [
+                west[i][0], above[i], east[i][0], west[i][1], east[i][1], west[i][2], below[i],
+                east[i][2],
+            ];
+            // Four bit planes of the neighbour count, enough for 0..=8.
+            let mut count = [0u64; 4];
+            for word in neighbours {
+                let mut carry = word;
+                for bit in count.iter_mut() {
+                    let overflow = *bit & carry;
+                    *bit ^= carry;
+                    carry = overflow;
+                }
+            }
+
+            let alive = centre[i];
+            let mut cells = 0;
+            for n in 0..=8 {
+                let equal = count.iter().enumerate().fold(!0u64, |acc, (b, &bit)| {
+                    acc & if n >> b & 1 == 1 { bit } else { !bit }
+                });
+                cells |= equal & (born[n] & !alive | survive[n] & alive);
+            }
+            next[i] = cells;
+        }
+        next[row_len - 1] &= valid;
+    }
+}
+
+impl Simulator for PackedSimulator {
+    fn step(&mut self) {
+        self.generation += 1;
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+
+        let bands = self.threads.clamp(1, (self.height / MIN_BAND_ROWS).max(1));
+        let band_rows = self.height.div_ceil(bands);
+        let halos: Vec<Halo> = (0..self.height)
+            .step_by(band_rows)
+            .map(|top| {
+                let bottom = (top + band_rows).min(self.height);
+                Halo {
+                    above: self.halo_row(top as isize - 1),
+                    below: self.halo_row(bottom as isize),
+                    edges: (top..bottom).map(|y| self.edge_bits(y)).collect(),
+                }
+            })
+            .collect();
+
+        let (rule, width) = (self.rule, self.width);
+        let band_len = band_rows * self.row_len;
+        let mut bands = self
+            .cells
+            .chunks(band_len)
+            .zip(self.back.chunks_mut(band_len))
+            .zip(&halos);
+        // The first band runs on this thread.
+        let ((first, first_out), first_halo) = bands.next().unwrap();
+        thread::scope(|scope| {
+            for ((band, out), halo) in bands {
+                scope.spawn(move || step_band(rule, width, band, halo, out));
+            }
+            step_band(rule, width, first, first_halo, first_out);
+        });
+        std::mem::swap(&mut self.cells, &mut self.back);
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn read_cells(&mut self) -> Grid {
+        let mut grid = Grid::empty(self.width as u32, self.height as u32);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                grid.set(x as u32, y as u32, self.get(x, y) as u32);
+            }
+        }
+        grid
+    }
+
+    fn write_cells(&mut self, grid: &Grid) {
+        self.width = grid.width as usize;
+        self.height = grid.height as usize;
+        self.row_len = self.width.div_ceil(WORD_BITS);
+        self.cells = vec![0; self.row_len * self.height];
+        for y in 0..grid.height {
+            for x in (0..grid.width).filter(|&x| grid.get(x, y) != 0) {
+                let x = x as usize;
+                self.cells[y as usize * self.row_len + x / WORD_BITS] |= 1 << (x % WORD_BITS);
+            }
+        }
+        self.back = self.cells.clone();
+    }
+
+    fn set_rule(&mut self, rule: Rule) {
+        assert!(rule.is_life_like(), "{rule} is not Life-like");
+        self.rule = rule;
+    }
+
+    fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+}
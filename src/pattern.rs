@@ -0,0 +1,205 @@
+use std::{fmt, path::Path};
+
+use crate::{grid::Grid, rule::Rule, topology::BoundedGrid};
+
+pub mod life;
+pub mod macrocell;
+pub mod plaintext;
+pub mod rle;
+
+/// Pattern file formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Rle,
+    /// `.cells`
+    Plaintext,
+    Life105,
+    Life106,
+    /// Golly `.mc`
+    Macrocell,
+}
+
+impl Format {
+    /// By file extension. `.lif` and `.life` are shared by both Life versions, so give `None`.
+    pub fn from_extension(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "rle" => Some(Format::Rle),
+            "cells" => Some(Format::Plaintext),
+            "mc" => Some(Format::Macrocell),
+            _ => None,
+        }
+    }
+
+    /// By looking at the first lines of a file.
+    pub fn sniff(s: &str) -> Option<Format> {
+        let s = s.trim_start_matches('\u{feff}');
+        let first = s.lines().map(str::trim).find(|line| !line.is_empty())?;
+        if first.starts_with(life::HEADER_105) {
+            return Some(Format::Life105);
+        }
+        if first.starts_with(life::HEADER_106) {
+            return Some(Format::Life106);
+        }
+        if first.starts_with(macrocell::HEADER) {
+            return Some(Format::Macrocell);
+        }
+
+        let body = s
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('!'))
+            .unwrap_or("");
+        if body.starts_with('x') && body.contains('=') {
+            Some(Format::Rle)
+        } else if first.starts_with('!') || body.chars().all(|c| matches!(c, '.' | 'O' | '*')) {
+            Some(Format::Plaintext)
+        } else {
+            None
+        }
+    }
+
+    /// Formats with only live and dead cells, their writers drop cells of any state but 1.
+    pub fn two_state(self) -> bool {
+        matches!(self, Format::Plaintext | Format::Life105 | Format::Life106)
+    }
+
+    /// By extension if it is conclusive, by contents otherwise.
+    pub fn detect(path: &Path, s: &str) -> Option<Format> {
+        Self::from_extension(path).or_else(|| Self::sniff(s))
+    }
+}
+
+/// A pattern read from or written to a file, cells are stored in a grid exactly its size.
+#[derive(Clone)]
+pub struct Pattern {
+    pub grid: Grid,
+    pub rule: Option<Rule>,
+    /// Golly bounded grid given after the rule, `B3/S23:T100,100`.
+    pub bounds: Option<BoundedGrid>,
+    pub name: Option<String>,
+    pub comments: Vec<String>,
+}
+
+/// Malformed pattern input, `line` and `column` are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl PatternError {
+    pub fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+/// Most cells a pattern file is read into a grid of, 8192 x 8192 or the like. Patterns larger
+/// than that are only read from macrocell files, without a grid.
+pub const MAX_CELLS: u64 = 1 << 26;
+
+/// An empty grid for a pattern `width` x `height`, an error at `line` if it would have more
+/// than `MAX_CELLS` cells.
+fn empty_grid(width: u64, height: u64, line: usize) -> Result<Grid, PatternError> {
+    match width.max(1).checked_mul(height.max(1)) {
+        Some(cells) if cells <= MAX_CELLS => Ok(Grid::empty(width as u32, height as u32)),
+        _ => Err(PatternError::new(
+            line,
+            1,
+            format!("{width} x {height} is more than {MAX_CELLS} cells"),
+        )),
+    }
+}
+
+impl Pattern {
+    pub fn parse(format: Format, s: &str) -> Result<Pattern, PatternError> {
+        match format {
+            Format::Rle => rle::parse(s),
+            Format::Plaintext => plaintext::parse(s),
+            Format::Life105 => life::parse_105(s),
+            Format::Life106 => life::parse_106(s),
+            Format::Macrocell => macrocell::parse(s),
+        }
+    }
+
+    /// Parse the contents of the file at `path`, whatever its format.
+    pub fn parse_detect(path: &Path, s: &str) -> Result<Pattern, PatternError> {
+        let format = Format::detect(path, s)
+            .ok_or_else(|| PatternError::new(1, 1, "unrecognised pattern format"))?;
+        Self::parse(format, s)
+    }
+
+    pub fn write(&self, format: Format) -> String {
+        match format {
+            Format::Rle => rle::write(self),
+            Format::Plaintext => plaintext::write(self),
+            Format::Life105 => life::write_105(self),
+            Format::Life106 => life::write_106(self),
+            Format::Macrocell => macrocell::write(self),
+        }
+    }
+
+    pub fn new(grid: Grid) -> Self {
+        Self {
+            grid,
+            rule: None,
+            bounds: None,
+            name: None,
+            comments: vec![],
+        }
+    }
+
+    /// The smallest part of `grid` holding every live cell, with its offset.
+    /// `None` if there are no live cells.
+    pub fn bounding_box(grid: &Grid) -> Option<(Self, (u32, u32))> {
+        let (mut min_x, mut min_y) = (u32::MAX, u32::MAX);
+        let (mut max_x, mut max_y) = (0, 0);
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                if grid.get(x, y) != 0 {
+                    min_x = min_x.min(x);
+                    min_y = min_y.min(y);
+                    max_x = max_x.max(x);
+                    max_y = max_y.max(y);
+                }
+            }
+        }
+        if min_x > max_x {
+            return None;
+        }
+
+        let mut cropped = Grid::empty(max_x - min_x + 1, max_y - min_y + 1);
+        for y in 0..cropped.height {
+            for x in 0..cropped.width {
+                cropped.set(x, y, grid.get(min_x + x, min_y + y));
+            }
+        }
+        Some((Self::new(cropped), (min_x, min_y)))
+    }
+
+    /// Copy the cells into `grid` with the top left corner at `x`, `y`, cells that would land
+    /// outside `grid` are dropped.
+    pub fn place(&self, grid: &mut Grid, x: u32, y: u32) {
+        for py in 0..self.grid.height {
+            for px in 0..self.grid.width {
+                let (gx, gy) = (x as u64 + px as u64, y as u64 + py as u64);
+                if gx < grid.width as u64 && gy < grid.height as u64 {
+                    grid.set(gx as u32, gy as u32, self.grid.get(px, py));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Life 1.05 blocks and Life 1.06 coordinate lists.
+
+use crate::{grid::Grid, rule::Rule};
+
+use super::{Pattern, PatternError};
+
+pub const HEADER_105: &str = "#Life 1.05";
+pub const HEADER_106: &str = "#Life 1.06";
+
+pub fn parse_105(s: &str) -> Result<Pattern, PatternError> {
+    let mut cells = vec![];
+    let mut rule = None;
+    let mut comments = vec![];
+    // Top left of the current `#P` block and the row within it.
+    let mut block = None;
+
+    for (i, line) in body(s, HEADER_105)? {
+        let line = line.trim_end();
+        let error = |column: usize, message: String| PatternError::new(i + 1, column, message);
+        if let Some(rest) = line.strip_prefix('#') {
+            let (tag, text) = rest.split_at(rest.chars().next().map_or(0, char::len_utf8));
+            let text = text.trim();
+            match tag {
+                "D" | "C" => comments.push(text.to_string()),
+                "N" => rule = Some(Rule::LIFE),
+                "R" => rule = Some(Rule::resolve(text).map_err(|e| error(4, format!("{e}")))?),
+                "P" => {
+                    let (x, y) = parse_coordinates(text)
+                        .ok_or_else(|| error(4, format!("expected `#P x y`, found `#P {text}`")))?;
+                    block = Some((x, y, 0));
+                }
+                _ => return Err(error(1, format!("unknown line `#{tag}`"))),
+            }
+            continue;
+        }
+
+        let (bx, by, row) = block.get_or_insert((0, 0, 0));
+        for (j, c) in line.chars().enumerate() {
+            match c {
+                '.' => {}
+                '*' => cells.push((*bx + j as i64, *by + *row)),
+                c => return Err(error(j + 1, format!("unexpected `{c}`"))),
+            }
+        }
+        *row += 1;
+    }
+
+    let mut pattern = from_cells(&cells)?;
+    pattern.rule = rule;
+    pattern.comments = comments;
+    Ok(pattern)
+}
+
+pub fn parse_106(s: &str) -> Result<Pattern, PatternError> {
+    let mut cells = vec![];
+    for (i, line) in body(s, HEADER_106)? {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let cell = parse_coordinates(line).ok_or_else(|| {
+            PatternError::new(i + 1, 1, format!("expected `x y`, found `{line}`"))
+        })?;
+        cells.push(cell);
+    }
+    from_cells(&cells)
+}
+
+/// The numbered lines after the `header` line, which may follow blank lines and a byte order
+/// mark.
+fn body<'a>(
+    s: &'a str,
+    header: &str,
+) -> Result<impl Iterator<Item = (usize, &'a str)>, PatternError> {
+    let mut lines = s.trim_start_matches('\u{feff}').lines().enumerate();
+    loop {
+        match lines.next() {
+            Some((_, line)) if line.trim().is_empty() => {}
+            Some((_, line)) if line.trim().starts_with(header) => return Ok(lines),
+            Some((i, line)) => {
+                let message = format!("expected `{header}`, found `{}`", line.trim());
+                return Err(PatternError::new(i + 1, 1, message));
+            }
+            None => return Err(PatternError::new(1, 1, format!("missing `{header}`"))),
+        }
+    }
+}
+
+fn parse_coordinates(s: &str) -> Option<(i64, i64)> {
+    let mut it = s.split_whitespace().map(str::parse);
+    match (it.next(), it.next(), it.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) => Some((x, y)),
+        _ => None,
+    }
+}
+
+/// Shift live cells with arbitrary coordinates into a grid just big enough to hold them.
+fn from_cells(cells: &[(i64, i64)]) -> Result<Pattern, PatternError> {
+    if cells.is_empty() {
+        return Ok(Pattern::new(Grid::empty(0, 0)));
+    }
+    let min_x = cells.iter().map(|c| c.0).min().unwrap();
+    let min_y = cells.iter().map(|c| c.1).min().unwrap();
+    let max_x = cells.iter().map(|c| c.0).max().unwrap();
+    let max_y = cells.iter().map(|c| c.1).max().unwrap();
+
+    let size = |min: i64, max: i64| max.abs_diff(min).saturating_add(1);
+    let mut grid = super::empty_grid(size(min_x, max_x), size(min_y, max_y), 1)?;
+    for &(x, y) in cells {
+        grid.set(x.abs_diff(min_x) as u32, y.abs_diff(min_y) as u32, 1);
+    }
+    Ok(Pattern::new(grid))
+}
+
+/// Coordinates relative to the pattern's centre, as Life 1.05/1.06 files usually are.
+fn centred(grid: &Grid) -> (i64, i64) {
+    (-(grid.width as i64 / 2), -(grid.height as i64 / 2))
+}
+
+/// Only cells in state 1 are written live, as in `write_106`.
+pub fn write_105(pattern: &Pattern) -> String {
+    let grid = &pattern.grid;
+    let mut out = format!("{HEADER_105}\n");
+    for comment in pattern.name.iter().chain(&pattern.comments) {
+        out += &format!("#D {comment}\n");
+    }
+    match pattern.rule {
+        Some(Rule::LIFE) | None => out += "#N\n",
+        // Read back by `Rule::resolve` like any other rulestring.
+        Some(rule) if !rule.is_life_like() => out += &format!("#R {rule}\n"),
+        Some(rule) => {
+            let counts = |mask: u32| -> String {
+                (0..=8)
+                    .filter(|n| mask >> n & 1 == 1)
+                    .map(|n| char::from(b'0' + n as u8))
+                    .collect()
+            };
+            out += &format!("#R {}/{}\n", counts(rule.survival), counts(rule.birth));
+        }
+    }
+
+    let (x, y) = centred(grid);
+    out += &format!("#P {x} {y}\n");
+    for y in 0..grid.height {
+        out.extend((0..grid.width).map(|x| if grid.get(x, y) == 1 { '*' } else { '.' }));
+        out += "\n";
+    }
+    out
+}
+
+/// Only cells in state 1 are written, other states have no place in the format.
+pub fn write_106(pattern: &Pattern) -> String {
+    let grid = &pattern.grid;
+    let mut out = format!("{HEADER_106}\n");
+    let (ox, oy) = centred(grid);
+    for y in 0..grid.height {
+        for x in 0..grid.width {
+            if grid.get(x, y) == 1 {
+                out += &format!("{} {}\n", ox + x as i64, oy + y as i64);
+            }
+        }
+    }
+    out
+}
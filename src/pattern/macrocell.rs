@@ -0,0 +1,443 @@
+//! Golly macrocell `.mc` files, a quadtree of shared nodes.
+
+use std::collections::HashMap;
+
+use crate::{grid::Grid, rule::Rule, topology::BoundedGrid};
+
+use super::{Pattern, PatternError};
+
+pub const HEADER: &str = "[M2]";
+
+/// Smallest and largest `(x, y)` of a bounding box.
+type Corners = ((u64, u64), (u64, u64));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Node {
+    /// Two-state 8x8 leaf, one byte per row from the top, bit `x` set for a live cell.
+    Leaf([u8; 8]),
+    /// Multi-state 2x2 node, states of the nw, ne, sw and se cells.
+    Cells([u32; 4]),
+    /// A `2^level` square made of nw, ne, sw and se children. Children are 1-based node
+    /// numbers, 0 for an empty child.
+    Inner { level: u32, children: [usize; 4] },
+}
+
+impl Node {
+    pub fn level(&self) -> u32 {
+        match self {
+            Node::Leaf(_) => 3,
+            Node::Cells(_) => 1,
+            Node::Inner { level, .. } => *level,
+        }
+    }
+}
+
+/// A parsed macrocell file. The last node is the root.
+pub struct Macrocell {
+    pub nodes: Vec<Node>,
+    pub rule: Option<Rule>,
+    pub bounds: Option<BoundedGrid>,
+    pub generation: Option<u64>,
+    pub comments: Vec<String>,
+}
+
+impl Macrocell {
+    pub fn parse(s: &str) -> Result<Self, PatternError> {
+        let mut macrocell = Macrocell {
+            nodes: vec![],
+            rule: None,
+            bounds: None,
+            generation: None,
+            comments: vec![],
+        };
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim_end();
+            let error = |column: usize, message: String| PatternError::new(i + 1, column, message);
+            if i == 0 {
+                if !line.starts_with(HEADER) {
+                    return Err(error(1, format!("expected `{HEADER}`")));
+                }
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix('#') {
+                let (tag, text) = rest.split_at(rest.chars().next().map_or(0, char::len_utf8));
+                let text = text.trim();
+                match tag {
+                    "R" => {
+                        let (rule, bounds) =
+                            Rule::parse_bounded(text).map_err(|e| error(4, format!("{e}")))?;
+                        macrocell.rule = Some(rule);
+                        macrocell.bounds = bounds;
+                    }
+                    "G" => {
+                        let generation = text
+                            .parse()
+                            .map_err(|_| error(4, format!("bad generation `{text}`")))?;
+                        macrocell.generation = Some(generation);
+                    }
+                    "C" | "N" | "D" => macrocell.comments.push(text.to_string()),
+                    _ => {}
+                }
+                continue;
+            }
+            if line.is_empty() {
+                continue;
+            }
+
+            let node =
+                match line.chars().next() {
+                    Some('.' | '*' | '$') => Self::parse_leaf(line)
+                        .map_err(|(column, message)| error(column, message))?,
+                    _ => parse_inner(line, macrocell.nodes.len())
+                        .map_err(|message| error(1, message))?,
+                };
+            if let Node::Inner { level, children } = node {
+                for child in children.into_iter().filter(|&c| c != 0) {
+                    let child_level = macrocell.nodes[child - 1].level();
+                    if child_level != level - 1 {
+                        return Err(error(
+                            1,
+                            format!("level {level} node with a level {child_level} child"),
+                        ));
+                    }
+                }
+            }
+            macrocell.nodes.push(node);
+        }
+
+        if macrocell.nodes.is_empty() {
+            return Err(PatternError::new(1, 1, "no nodes"));
+        }
+        Ok(macrocell)
+    }
+
+    fn parse_leaf(line: &str) -> Result<Node, (usize, String)> {
+        let mut rows = [0u8; 8];
+        let (mut x, mut y) = (0, 0);
+        for (i, c) in line.chars().enumerate() {
+            if y >= 8 || (x >= 8 && c != '$') {
+                return Err((i + 1, "leaf larger than 8x8".into()));
+            }
+            match c {
+                '.' => x += 1,
+                '*' => {
+                    rows[y] |= 1 << x;
+                    x += 1;
+                }
+                '$' => {
+                    x = 0;
+                    y += 1;
+                }
+                c => return Err((i + 1, format!("unexpected `{c}`"))),
+            }
+        }
+        Ok(Node::Leaf(rows))
+    }
+
+    pub fn root(&self) -> &Node {
+        self.nodes.last().unwrap()
+    }
+
+    /// Every non-empty cell as `(x, y, state)` from the root's top left corner.
+    pub fn cells(&self) -> Vec<(u64, u64, u32)> {
+        let mut cells = vec![];
+        self.for_each_cell(|x, y, state| cells.push((x, y, state)));
+        cells
+    }
+
+    /// Call `f` with every non-empty cell, subtrees without one are never entered.
+    pub fn for_each_cell(&self, mut f: impl FnMut(u64, u64, u32)) {
+        let boxes = self.boxes();
+        self.visit(&boxes, self.nodes.len(), 0, 0, &mut f);
+    }
+
+    fn visit(
+        &self,
+        boxes: &[Option<Corners>],
+        index: usize,
+        x: u64,
+        y: u64,
+        f: &mut impl FnMut(u64, u64, u32),
+    ) {
+        if boxes[index].is_none() {
+            return;
+        }
+        match self.nodes[index - 1] {
+            Node::Leaf(rows) => {
+                for (dy, row) in rows.iter().enumerate() {
+                    for dx in (0..8).filter(|dx| row >> dx & 1 == 1) {
+                        f(x + dx, y + dy as u64, 1);
+                    }
+                }
+            }
+            Node::Cells(states) => {
+                for (i, &state) in states.iter().enumerate() {
+                    if state != 0 {
+                        f(x + (i % 2) as u64, y + (i / 2) as u64, state);
+                    }
+                }
+            }
+            Node::Inner { level, children } => {
+                let half = 1 << (level - 1);
+                for (i, &child) in children.iter().enumerate() {
+                    let (dx, dy) = ((i % 2) as u64 * half, (i / 2) as u64 * half);
+                    self.visit(boxes, child, x + dx, y + dy, f);
+                }
+            }
+        }
+    }
+
+    /// Smallest and largest corner of the non-empty cells from the root's top left corner,
+    /// `None` if there are none. Worked out from the tree, not the cells.
+    pub fn bounding_box(&self) -> Option<((u64, u64), (u64, u64))> {
+        *self.boxes().last().unwrap()
+    }
+
+    /// Bounding box of each node from its own top left corner, by node number. Children come
+    /// before their parents, so one pass over the nodes will do.
+    fn boxes(&self) -> Vec<Option<Corners>> {
+        let union = |a: Option<Corners>, b: Corners| match a {
+            Some((min, max)) => Some((
+                (min.0.min(b.0 .0), min.1.min(b.0 .1)),
+                (max.0.max(b.1 .0), max.1.max(b.1 .1)),
+            )),
+            None => Some(b),
+        };
+        let mut boxes: Vec<Option<Corners>> = vec![None];
+        for node in &self.nodes {
+            let mut bounds = None;
+            match *node {
+                Node::Leaf(rows) => {
+                    for (y, row) in (0..).zip(rows) {
+                        for x in (0..8).filter(|x| row >> x & 1 == 1) {
+                            bounds = union(bounds, ((x, y), (x, y)));
+                        }
+                    }
+                }
+                Node::Cells(states) => {
+                    for (i, state) in (0..).zip(states) {
+                        if state != 0 {
+                            let (x, y) = (i % 2, i / 2);
+                            bounds = union(bounds, ((x, y), (x, y)));
+                        }
+                    }
+                }
+                Node::Inner { level, children } => {
+                    let half = 1 << (level - 1);
+                    for (i, child) in (0..).zip(children) {
+                        if let Some((min, max)) = boxes[child] {
+                            let (dx, dy) = (i % 2 * half, i / 2 * half);
+                            bounds =
+                                union(bounds, ((min.0 + dx, min.1 + dy), (max.0 + dx, max.1 + dy)));
+                        }
+                    }
+                }
+            }
+            boxes.push(bounds);
+        }
+        boxes
+    }
+
+    /// Non-empty cells, saturating at `u64::MAX`. Counted per node, not per cell.
+    pub fn population(&self) -> u64 {
+        let mut populations = vec![0u64];
+        for node in &self.nodes {
+            populations.push(match *node {
+                Node::Leaf(rows) => rows.iter().map(|row| row.count_ones() as u64).sum(),
+                Node::Cells(states) => states.iter().filter(|&&state| state != 0).count() as u64,
+                Node::Inner { children, .. } => children
+                    .iter()
+                    .fold(0u64, |sum, &child| sum.saturating_add(populations[child])),
+            });
+        }
+        *populations.last().unwrap()
+    }
+
+    /// Live cells cropped to their bounding box, an error if that is more than
+    /// `MAX_CELLS`. Larger trees load straight into `HashLife` or `SparseLife` with their
+    /// `from_macrocell`.
+    pub fn to_pattern(&self) -> Result<Pattern, PatternError> {
+        // An empty tree makes a 0 x 0 grid.
+        let ((min_x, min_y), (max_x, max_y)) = self.bounding_box().unwrap_or(((1, 1), (0, 0)));
+        let mut grid = super::empty_grid(max_x + 1 - min_x, max_y + 1 - min_y, 1)?;
+        self.for_each_cell(|x, y, state| grid.set((x - min_x) as u32, (y - min_y) as u32, state));
+        let mut pattern = Pattern::new(grid);
+        pattern.rule = self.rule;
+        pattern.bounds = self.bounds;
+        pattern.comments = self.comments.clone();
+        Ok(pattern)
+    }
+
+    /// Build the canonical tree for a grid, identical subtrees are stored once.
+    /// Multi-state nodes are used only if some cell is above 1.
+    pub fn from_grid(grid: &Grid) -> Self {
+        let multi_state = grid.cell_arr.iter().any(|&v| v > 1);
+        let min_level = if multi_state { 1 } else { 3 };
+        let mut level = min_level;
+        while (1u64 << level) < grid.width.max(grid.height) as u64 {
+            level += 1;
+        }
+
+        let mut builder = Builder {
+            grid,
+            nodes: vec![],
+            index: HashMap::new(),
+        };
+        if builder.build(level, 0, 0, min_level) == 0 {
+            // Files always have at least a root.
+            builder.nodes.push(Node::Inner {
+                level: level + 1,
+                children: [0; 4],
+            });
+        }
+
+        Macrocell {
+            nodes: builder.nodes,
+            rule: None,
+            bounds: None,
+            generation: None,
+            comments: vec![],
+        }
+    }
+
+    pub fn write(&self) -> String {
+        let mut out = format!("{HEADER} (game-of-life)\n");
+        if let Some(rule) = self.rule {
+            out += &format!("#R {}\n", rule.to_bounded_string(self.bounds));
+        }
+        if let Some(generation) = self.generation {
+            out += &format!("#G {generation}\n");
+        }
+        for comment in &self.comments {
+            out += &format!("#C {comment}\n");
+        }
+
+        for node in &self.nodes {
+            match node {
+                Node::Leaf(rows) => {
+                    let end = rows.iter().rposition(|&row| row != 0).map_or(0, |y| y + 1);
+                    for row in &rows[..end] {
+                        let width = 8 - row.leading_zeros() as usize;
+                        out.extend((0..width).map(|x| if row >> x & 1 == 1 { '*' } else { '.' }));
+                        out += "$";
+                    }
+                }
+                Node::Cells(states) => {
+                    out += &format!("1 {} {} {} {}", states[0], states[1], states[2], states[3])
+                }
+                Node::Inner { level, children } => {
+                    out += &format!(
+                        "{level} {} {} {} {}",
+                        children[0], children[1], children[2], children[3]
+                    )
+                }
+            }
+            out += "\n";
+        }
+        out
+    }
+}
+
+fn parse_inner(line: &str, count: usize) -> Result<Node, String> {
+    let numbers = line
+        .split_whitespace()
+        .map(|n| n.parse::<u64>().map_err(|_| format!("bad number `{n}`")))
+        .collect::<Result<Vec<_>, _>>()?;
+    let [level, a, b, c, d] = numbers[..] else {
+        return Err(format!("expected 5 numbers, found {}", numbers.len()));
+    };
+    match level {
+        1 => {
+            let state = |v: u64| u32::try_from(v).map_err(|_| format!("bad state {v}"));
+            Ok(Node::Cells([state(a)?, state(b)?, state(c)?, state(d)?]))
+        }
+        2..=63 => {
+            let children = [a, b, c, d].map(|child| child as usize);
+            if let Some(child) = children.iter().find(|&&child| child > count) {
+                return Err(format!("node {child} used before it is defined"));
+            }
+            Ok(Node::Inner {
+                level: level as u32,
+                children,
+            })
+        }
+        _ => Err(format!("bad level {level}")),
+    }
+}
+
+struct Builder<'a> {
+    grid: &'a Grid,
+    nodes: Vec<Node>,
+    index: HashMap<Node, usize>,
+}
+
+impl Builder<'_> {
+    /// Node number for the `2^level` square at `x`, `y`, 0 if empty.
+    fn build(&mut self, level: u32, x: u64, y: u64, min_level: u32) -> usize {
+        if x >= self.grid.width as u64 || y >= self.grid.height as u64 {
+            return 0;
+        }
+        let cell = |dx: u64, dy: u64| {
+            let (cx, cy) = (x + dx, y + dy);
+            if cx < self.grid.width as u64 && cy < self.grid.height as u64 {
+                self.grid.get(cx as u32, cy as u32)
+            } else {
+                0
+            }
+        };
+
+        let node = if level == min_level && min_level == 3 {
+            let mut rows = [0u8; 8];
+            for (dy, row) in rows.iter_mut().enumerate() {
+                for dx in 0..8 {
+                    if cell(dx, dy as u64) != 0 {
+                        *row |= 1 << dx;
+                    }
+                }
+            }
+            if rows == [0; 8] {
+                return 0;
+            }
+            Node::Leaf(rows)
+        } else if level == min_level {
+            let states = [cell(0, 0), cell(1, 0), cell(0, 1), cell(1, 1)];
+            if states == [0; 4] {
+                return 0;
+            }
+            Node::Cells(states)
+        } else {
+            let half = 1 << (level - 1);
+            let children = [(0, 0), (half, 0), (0, half), (half, half)]
+                .map(|(dx, dy)| self.build(level - 1, x + dx, y + dy, min_level));
+            if children == [0; 4] {
+                return 0;
+            }
+            Node::Inner { level, children }
+        };
+
+        if let Some(&index) = self.index.get(&node) {
+            return index;
+        }
+        self.nodes.push(node);
+        self.index.insert(node, self.nodes.len());
+        self.nodes.len()
+    }
+}
+
+pub fn parse(s: &str) -> Result<Pattern, PatternError> {
+    Macrocell::parse(s)?.to_pattern()
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let mut macrocell = Macrocell::from_grid(&pattern.grid);
+    macrocell.rule = pattern.rule;
+    macrocell.bounds = pattern.bounds;
+    macrocell.comments = pattern
+        .name
+        .iter()
+        .chain(&pattern.comments)
+        .cloned()
+        .collect();
+    macrocell.write()
+}
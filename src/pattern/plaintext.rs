@@ -0,0 +1,59 @@
+//! Plaintext `.cells` patterns, `.` dead and `O` live with `!` comment lines.
+
+use super::{Pattern, PatternError};
+
+pub fn parse(s: &str) -> Result<Pattern, PatternError> {
+    let mut name = None;
+    let mut comments = vec![];
+    let mut rows = vec![];
+    for (i, line) in s.lines().enumerate() {
+        let line = line.trim_end();
+        if let Some(comment) = line.strip_prefix('!') {
+            match comment.strip_prefix("Name:") {
+                Some(n) => name = Some(n.trim().to_string()),
+                None => comments.push(comment.trim().to_string()),
+            }
+            continue;
+        }
+        let row = line
+            .chars()
+            .enumerate()
+            .map(|(j, c)| match c {
+                '.' => Ok(0),
+                'O' | '*' => Ok(1),
+                c => Err(PatternError::new(i + 1, j + 1, format!("unexpected `{c}`"))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.push(row);
+    }
+
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut grid = super::empty_grid(width as u64, rows.len() as u64, 1)?;
+    for (y, row) in rows.iter().enumerate() {
+        for (x, &v) in row.iter().enumerate() {
+            grid.set(x as u32, y as u32, v);
+        }
+    }
+
+    let mut pattern = Pattern::new(grid);
+    pattern.name = name;
+    pattern.comments = comments;
+    Ok(pattern)
+}
+
+/// Only cells in state 1 are written live.
+pub fn write(pattern: &Pattern) -> String {
+    let grid = &pattern.grid;
+    let mut out = String::new();
+    if let Some(name) = &pattern.name {
+        out += &format!("!Name: {name}\n");
+    }
+    for comment in &pattern.comments {
+        out += &format!("!{comment}\n");
+    }
+    for y in 0..grid.height {
+        out.extend((0..grid.width).map(|x| if grid.get(x, y) == 1 { 'O' } else { '.' }));
+        out += "\n";
+    }
+    out
+}
@@ -0,0 +1,278 @@
+//! Golly / LifeWiki run length encoded patterns.
+
+use crate::{grid::Grid, rule::Rule};
+
+use super::{Pattern, PatternError};
+
+/// Longest line `write` produces, as recommended by the format.
+const LINE_LEN: usize = 70;
+
+pub fn parse(s: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::new(Grid::empty(0, 0));
+    let mut lines = s.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+    let (header_line, header) = loop {
+        match lines.next() {
+            Some((i, line)) => {
+                let line = line.trim();
+                if let Some(comment) = line.strip_prefix('#') {
+                    read_comment(&mut pattern, comment);
+                } else if !line.is_empty() {
+                    break (i, line);
+                }
+            }
+            None => return Err(PatternError::new(1, 1, "missing `x = .., y = ..` header")),
+        }
+    };
+    read_header(&mut pattern, header_line, header)?;
+
+    let mut reader = BodyReader::new(&mut pattern.grid);
+    for (i, line) in lines {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        if reader.read_line(i, line)? {
+            return Ok(pattern);
+        }
+    }
+    // Golly accepts a missing `!`.
+    Ok(pattern)
+}
+
+fn read_comment(pattern: &mut Pattern, comment: &str) {
+    let (tag, text) = comment.split_at(comment.chars().next().map_or(0, char::len_utf8));
+    let text = text.trim().to_string();
+    match tag {
+        "N" => pattern.name = Some(text),
+        "C" | "c" => pattern.comments.push(text),
+        _ => {}
+    }
+}
+
+fn read_header(pattern: &mut Pattern, line: usize, header: &str) -> Result<(), PatternError> {
+    let (mut width, mut height) = (None, None);
+    // The rule runs to the end of the line since bounded grids have commas, `B3/S23:T100,100`.
+    let mut fields: Vec<&str> = header.split(',').collect();
+    if let Some(i) = fields
+        .iter()
+        .position(|f| f.trim_start().starts_with("rule"))
+    {
+        let start = fields[..i].iter().map(|f| f.len() + 1).sum();
+        fields.truncate(i);
+        fields.push(&header[start..]);
+    }
+
+    let mut column = 1;
+    for field in fields {
+        let error = |message: String| PatternError::new(line, column, message);
+        let (key, value) = field
+            .split_once('=')
+            .ok_or_else(|| error(format!("expected `key = value`, found `{}`", field.trim())))?;
+        let value = value.trim();
+        match key.trim() {
+            "x" => {
+                width = Some(
+                    value
+                        .parse()
+                        .map_err(|_| error(format!("bad width `{value}`")))?,
+                )
+            }
+            "y" => {
+                height = Some(
+                    value
+                        .parse()
+                        .map_err(|_| error(format!("bad height `{value}`")))?,
+                )
+            }
+            "rule" => {
+                let (rule, bounds) =
+                    Rule::parse_bounded(value).map_err(|e| error(format!("{e}")))?;
+                pattern.rule = Some(rule);
+                pattern.bounds = bounds;
+            }
+            key => return Err(error(format!("unknown header field `{key}`"))),
+        }
+        column += field.len() + 1;
+    }
+
+    match (width, height) {
+        (Some(width), Some(height)) => {
+            pattern.grid = super::empty_grid(width, height, line)?;
+            Ok(())
+        }
+        _ => Err(PatternError::new(line, 1, "header needs both x and y")),
+    }
+}
+
+struct BodyReader<'a> {
+    grid: &'a mut Grid,
+    x: u32,
+    y: u32,
+    count: Option<u32>,
+    /// `p`..`y` prefix of a multi-state cell.
+    prefix: Option<u32>,
+}
+
+impl<'a> BodyReader<'a> {
+    fn new(grid: &'a mut Grid) -> Self {
+        Self {
+            grid,
+            x: 0,
+            y: 0,
+            count: None,
+            prefix: None,
+        }
+    }
+
+    /// Returns true once `!` is reached.
+    fn read_line(&mut self, line: usize, s: &str) -> Result<bool, PatternError> {
+        for (i, c) in s.chars().enumerate() {
+            let error = |message: String| PatternError::new(line, i + 1, message);
+            if let Some(prefix) = self.prefix.take() {
+                match c {
+                    'A'..='X' => self
+                        .run(prefix * 24 + c as u32 - 'A' as u32 + 1)
+                        .map_err(error)?,
+                    _ => return Err(error(format!("expected a state letter A-X, found `{c}`"))),
+                }
+                continue;
+            }
+            match c {
+                '0'..='9' => {
+                    let digit = c as u32 - '0' as u32;
+                    let count = self
+                        .count
+                        .unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(digit));
+                    self.count = Some(count.ok_or_else(|| error("run count too large".into()))?);
+                }
+                'b' | '.' => self.run(0).map_err(error)?,
+                'o' => self.run(1).map_err(error)?,
+                'A'..='X' => self.run(c as u32 - 'A' as u32 + 1).map_err(error)?,
+                'p'..='y' => self.prefix = Some(c as u32 - 'p' as u32 + 1),
+                '$' => {
+                    let count = self.count.take().unwrap_or(1);
+                    self.y = self
+                        .y
+                        .checked_add(count)
+                        .filter(|&y| y <= self.grid.height)
+                        .ok_or_else(|| error(self.past_header()))?;
+                    self.x = 0;
+                }
+                '!' => return Ok(true),
+                c if c.is_whitespace() => {}
+                c => return Err(error(format!("unexpected `{c}`"))),
+            }
+        }
+        Ok(false)
+    }
+
+    fn run(&mut self, state: u32) -> Result<(), String> {
+        let count = self.count.take().unwrap_or(1);
+        let end = self
+            .x
+            .checked_add(count)
+            .filter(|&end| end <= self.grid.width)
+            .ok_or_else(|| self.past_header())?;
+        if state != 0 {
+            if self.y >= self.grid.height {
+                return Err(self.past_header());
+            }
+            for x in self.x..end {
+                self.grid.set(x, self.y, state);
+            }
+        }
+        self.x = end;
+        Ok(())
+    }
+
+    fn past_header(&self) -> String {
+        format!(
+            "cells past the {}x{} size in the header",
+            self.grid.width, self.grid.height
+        )
+    }
+}
+
+pub fn write(pattern: &Pattern) -> String {
+    let grid = &pattern.grid;
+    let mut out = String::new();
+    if let Some(name) = &pattern.name {
+        out += &format!("#N {name}\n");
+    }
+    for comment in &pattern.comments {
+        out += &format!("#C {comment}\n");
+    }
+    out += &format!("x = {}, y = {}", grid.width, grid.height);
+    if let Some(rule) = &pattern.rule {
+        out += &format!(", rule = {}", rule.to_bounded_string(pattern.bounds));
+    }
+    out += "\n";
+
+    let mut writer = BodyWriter::default();
+    let mut blank_rows = 0;
+    for y in 0..grid.height {
+        let row: Vec<u32> = (0..grid.width).map(|x| grid.get(x, y)).collect();
+        let Some(end) = row.iter().rposition(|&v| v != 0) else {
+            blank_rows += 1;
+            continue;
+        };
+        if y > blank_rows {
+            writer.push(blank_rows + 1, "$");
+        } else if blank_rows > 0 {
+            // Leading blank rows.
+            writer.push(blank_rows, "$");
+        }
+        blank_rows = 0;
+
+        let mut x = 0;
+        while x <= end {
+            let state = row[x];
+            let len = row[x..=end].iter().take_while(|&&v| v == state).count();
+            writer.push(len as u32, &state_tag(state));
+            x += len;
+        }
+    }
+    writer.push(1, "!");
+    out + &writer.finish()
+}
+
+fn state_tag(state: u32) -> String {
+    match state {
+        0 => "b".into(),
+        1 => "o".into(),
+        _ => {
+            let (prefix, letter) = ((state - 1) / 24, (state - 1) % 24);
+            let letter = char::from(b'A' + letter as u8);
+            match prefix {
+                0 => letter.to_string(),
+                _ => format!("{}{letter}", char::from(b'p' + prefix as u8 - 1)),
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct BodyWriter {
+    lines: Vec<String>,
+    line: String,
+}
+
+impl BodyWriter {
+    fn push(&mut self, count: u32, tag: &str) {
+        let token = match count {
+            1 => tag.to_string(),
+            n => format!("{n}{tag}"),
+        };
+        if self.line.len() + token.len() > LINE_LEN {
+            self.lines.push(std::mem::take(&mut self.line));
+        }
+        self.line += &token;
+    }
+
+    fn finish(mut self) -> String {
+        self.lines.push(self.line);
+        self.lines.join("\n") + "\n"
+    }
+}
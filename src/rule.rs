@@ -0,0 +1,372 @@
+use std::{
+    fmt::{self, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use crate::{tiling::Tiling, topology::BoundedGrid};
+
+pub mod golly;
+pub mod hensel;
+pub mod larger;
+pub mod table;
+pub mod tree;
+
+use hensel::Isotropic;
+use larger::{LargerThanLife, Neighbourhood};
+use tree::RuleTree;
+
+/// A Life-like rule, bit `n` of `birth`/`survival` set means `n` live neighbours apply.
+///
+/// Generations rules have more than 2 `states`: a live cell that does not survive decays
+/// through states 2, 3, .. back to 0, and those dying cells are not live neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    pub birth: u32,
+    pub survival: u32,
+    pub states: u32,
+    /// Counts over a bigger neighbourhood replacing `birth` and `survival`, which are then 0.
+    pub larger: Option<LargerThanLife>,
+    /// Conditions on which neighbours are live replacing `birth` and `survival`, which are
+    /// then 0. `None` when every count applies in all of its configurations or none.
+    pub isotropic: Option<Isotropic>,
+    /// Transitions of a Golly `.rule` file, any number of states on the Moore or von Neumann
+    /// neighbourhood. `birth` and `survival` are then 0. Interned by `RuleTree::intern`.
+    pub tree: Option<&'static RuleTree>,
+    /// Shape of the cells, `birth` and `survival` count up to `Tiling::neighbour_count`
+    /// neighbours. Only Life-like and Generations rules have other tilings than squares.
+    pub tiling: Tiling,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleError {
+    /// Not exactly two `/` separated parts.
+    Format,
+    /// A character that is not a neighbour count the tiling's cells can have.
+    Digit(char),
+    /// A Hensel letter that does not name a configuration of that many neighbours.
+    Letter(char, u32),
+    /// Both parts are `B` or both are `S`.
+    Duplicate(char),
+    /// A Generations state count that is not a number in 2..=256.
+    States(String),
+    /// A bad Larger than Life rule.
+    Larger(String),
+    /// A bad bounded-grid suffix after the `:`.
+    Bounds(String),
+    /// A `.rule` file that cannot be read or parsed.
+    File(String),
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleError::Format => write!(f, "expected a rule like B3/S23 or 23/3"),
+            RuleError::Digit(c) => write!(f, "invalid neighbour count '{c}'"),
+            RuleError::Letter(c, n) => write!(f, "no '{c}' configuration of {n} neighbours"),
+            RuleError::Duplicate(c) => write!(f, "'{c}' given twice"),
+            RuleError::States(s) => write!(f, "expected 2 to 256 states, found `{s}`"),
+            RuleError::Bounds(message) | RuleError::Larger(message) | RuleError::File(message) => {
+                write!(f, "{message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+impl Rule {
+    /// Conway's Life, B3/S23.
+    pub const LIFE: Rule = Rule {
+        birth: 1 << 3,
+        survival: 1 << 2 | 1 << 3,
+        states: 2,
+        larger: None,
+        isotropic: None,
+        tree: None,
+        tiling: Tiling::Square,
+    };
+
+    /// Most states a Generations rule can have, as in Golly.
+    pub const MAX_STATES: u32 = 256;
+
+    /// Two states and counts of the 3x3 neighbourhood of square cells, the only rules the
+    /// bit-packed, sparse and HashLife engines step.
+    pub fn is_life_like(&self) -> bool {
+        self.states == 2
+            && self.larger.is_none()
+            && self.isotropic.is_none()
+            && self.tree.is_none()
+            && self.tiling == Tiling::Square
+    }
+
+    /// The rule of a Golly `.rule` file.
+    pub fn from_tree(tree: RuleTree) -> Self {
+        Self {
+            birth: 0,
+            survival: 0,
+            states: tree.states,
+            larger: None,
+            isotropic: None,
+            tree: Some(tree.intern()),
+            tiling: Tiling::Square,
+        }
+    }
+
+    /// Radius and shape of the neighbourhood, and whether a live cell counts itself.
+    pub fn neighbourhood(&self) -> (u32, Neighbourhood, bool) {
+        match self.larger {
+            Some(larger) => (larger.range, larger.neighbourhood, larger.middle),
+            None => (1, Neighbourhood::Moore, false),
+        }
+    }
+
+    /// State of a cell in the next generation, `active_neighbours` includes the cell itself
+    /// when the neighbourhood's middle counts.
+    pub fn next(&self, state: u32, active_neighbours: u32) -> u32 {
+        let (born, survives) = match self.larger {
+            Some(LargerThanLife {
+                birth, survival, ..
+            }) => (
+                (birth.0..=birth.1).contains(&active_neighbours),
+                (survival.0..=survival.1).contains(&active_neighbours),
+            ),
+            None => (
+                (self.birth >> active_neighbours) & 1 == 1,
+                (self.survival >> active_neighbours) & 1 == 1,
+            ),
+        };
+        self.transition(state, born, survives)
+    }
+
+    /// State of a cell in the next generation looked up in `table` from `Rule::table`, by
+    /// the 3x3 block around it with bit `i` the cell `i` in reading order live.
+    pub fn next_in_table(&self, table: &[u32; 512], state: u32, block: usize) -> u32 {
+        let live = table[block] == 1;
+        self.transition(state, live, live)
+    }
+
+    fn transition(&self, state: u32, born: bool, survives: bool) -> u32 {
+        match state {
+            0 => born as u32,
+            1 if survives => 1,
+            // Live cells that do not survive and dying cells decay one state further.
+            state => (state + 1) % self.states,
+        }
+    }
+
+    /// 1 where the 3x3 block, bit `i` the cell `i` in reading order, has a live middle cell
+    /// next generation. For rules on the 3x3 neighbourhood, the lookup table `compute.wgsl`
+    /// steps isotropic rules with.
+    pub fn table(&self) -> [u32; 512] {
+        if let Some(isotropic) = &self.isotropic {
+            return isotropic.table();
+        }
+        std::array::from_fn(|block| {
+            let mask = match block >> 4 & 1 {
+                0 => self.birth,
+                _ => self.survival,
+            };
+            mask >> (block & !(1 << 4)).count_ones() & 1
+        })
+    }
+
+    /// The rule as `compute.wgsl` reads it.
+    pub fn uniform(&self) -> [u32; 14] {
+        let (root, neighbours) = self
+            .tree
+            .as_ref()
+            .map_or((0, 0), |tree| (tree.root, tree.neighbours));
+        let (range, neighbourhood, middle) = self.neighbourhood();
+        let (birth, survival) = self
+            .larger
+            .map_or(((0, 0), (0, 0)), |larger| (larger.birth, larger.survival));
+        [
+            self.birth,
+            self.survival,
+            self.states,
+            range,
+            neighbourhood as u32,
+            middle as u32,
+            birth.0,
+            birth.1,
+            survival.0,
+            survival.1,
+            root,
+            neighbours,
+            self.tiling.uniform(),
+            // Pads the struct to its 8 byte alignment.
+            0,
+        ]
+    }
+
+    /// The rule of the Golly `.rule` file at `path`.
+    pub fn load(path: &Path) -> Result<Rule, RuleError> {
+        let tree = golly::load(path).map_err(RuleError::File)?;
+        Ok(Self::from_tree(tree))
+    }
+
+    /// Parse a rulestring, or when `s` is not one load the `.rule` file it names: a path
+    /// ending in `.rule`, or a name such as `WireWorld` with a file in `golly::RULE_DIRS`.
+    pub fn resolve(s: &str) -> Result<Rule, RuleError> {
+        s.parse().or_else(|error| match golly::find(s) {
+            Some(path) => Self::load(&path),
+            None => Err(error),
+        })
+    }
+
+    /// Resolve a rule with an optional Golly bounded-grid suffix, `B3/S23:T100,100`.
+    pub fn parse_bounded(s: &str) -> Result<(Rule, Option<BoundedGrid>), RuleError> {
+        match s.split_once(':') {
+            Some((rule, bounds)) => Ok((
+                Self::resolve(rule)?,
+                Some(bounds.parse().map_err(RuleError::Bounds)?),
+            )),
+            None => Ok((Self::resolve(s)?, None)),
+        }
+    }
+
+    /// The rulestring with `bounds` appended the way `parse_bounded` reads it. Bounds Golly has
+    /// no form for are left off with a warning.
+    pub fn to_bounded_string(&self, bounds: Option<BoundedGrid>) -> String {
+        let mut s = self.to_string();
+        if let Some(bounds) = bounds {
+            if write!(s, ":{bounds}").is_err() {
+                log::warn!("{bounds:?} has no Golly form, writing {self} without it");
+                s = self.to_string();
+            }
+        }
+        s
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self::LIFE
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleError;
+
+    /// Accepts `B3/S23`, `S23/B3` and the bare survival/birth form `23/3`, with a third part
+    /// giving the states of a Generations rule, `B2/S/C3` or `345/2/4`. Larger than Life
+    /// rules are in Golly's notation, `R5,C0,M1,S34..58,B34..45,NM`. Counts may be followed
+    /// by Hensel letters for isotropic non-totalistic rules, `B2-a/S12`. A suffix puts the rule
+    /// on other cells than squares, `H` on hexagons and `L` or `LE` on triangles, with counts
+    /// 10 to 12 written `a` to `c`, `B3/S2abL`. Names of `.rule` files are left to
+    /// `Rule::resolve`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(larger) = LargerThanLife::parse(s) {
+            let (larger, states) = larger.map_err(RuleError::Larger)?;
+            return Ok(Self {
+                birth: 0,
+                survival: 0,
+                states,
+                larger: Some(larger),
+                isotropic: None,
+                tree: None,
+                tiling: Tiling::Square,
+            });
+        }
+        let (s, tiling) = Tiling::strip_suffix(s);
+        let (a, b) = s.split_once('/').ok_or(RuleError::Format)?;
+        let (b, states) = match b.split_once('/') {
+            Some((b, states)) => {
+                let count = states.strip_prefix(['C', 'c', 'G', 'g']).unwrap_or(states);
+                match count.parse() {
+                    Ok(states) if (2..=Self::MAX_STATES).contains(&states) => (b, states),
+                    _ => return Err(RuleError::States(states.to_string())),
+                }
+            }
+            None => (b, 2),
+        };
+
+        let mut birth = None;
+        let mut survival = None;
+        for (i, part) in [a, b].into_iter().enumerate() {
+            let (slot, name, counts) = match part.chars().next() {
+                Some('B' | 'b') => (&mut birth, 'B', &part[1..]),
+                Some('S' | 's') => (&mut survival, 'S', &part[1..]),
+                // Bare digits are survival/birth.
+                _ if i == 0 => (&mut survival, 'S', part),
+                _ => (&mut birth, 'B', part),
+            };
+            if slot.is_some() {
+                return Err(RuleError::Duplicate(name));
+            }
+            *slot = Some(counts);
+        }
+
+        let (Some(birth), Some(survival)) = (birth, survival) else {
+            return Err(RuleError::Format);
+        };
+        if tiling != Tiling::Square {
+            return Ok(Self {
+                birth: parse_counts(birth, tiling)?,
+                survival: parse_counts(survival, tiling)?,
+                states,
+                larger: None,
+                isotropic: None,
+                tree: None,
+                tiling,
+            });
+        }
+        let (birth, survival) = (
+            hensel::parse_conditions(birth)?,
+            hensel::parse_conditions(survival)?,
+        );
+        let isotropic = Isotropic { birth, survival };
+        let (birth, survival, isotropic) = match isotropic.totalistic() {
+            Some((birth, survival)) => (birth, survival, None),
+            None => (0, 0, Some(isotropic)),
+        };
+        Ok(Self {
+            birth,
+            survival,
+            states,
+            larger: None,
+            isotropic,
+            tree: None,
+            tiling,
+        })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(tree) = &self.tree {
+            return write!(f, "{}", tree.name);
+        }
+        if let Some(larger) = &self.larger {
+            return write!(f, "{}", larger.display(self.states));
+        }
+        let counts = |mask: u32| -> String {
+            (0..=self.tiling.neighbour_count())
+                .filter(|n| mask >> n & 1 == 1)
+                .map(|n| char::from_digit(n, 13).unwrap())
+                .collect()
+        };
+        match &self.isotropic {
+            Some(isotropic) => write!(
+                f,
+                "B{}/S{}",
+                hensel::display_conditions(&isotropic.birth),
+                hensel::display_conditions(&isotropic.survival)
+            )?,
+            None => write!(f, "B{}/S{}", counts(self.birth), counts(self.survival))?,
+        }
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        write!(f, "{}", self.tiling.suffix())
+    }
+}
+
+/// Bitmask of the neighbour counts in `s`, digits and `a` to `c` for 10 to 12.
+fn parse_counts(s: &str, tiling: Tiling) -> Result<u32, RuleError> {
+    s.chars().try_fold(0, |mask, c| match c.to_digit(13) {
+        Some(n) if n <= tiling.neighbour_count() => Ok(mask | 1 << n),
+        _ => Err(RuleError::Digit(c)),
+    })
+}
@@ -0,0 +1,119 @@
+//! Golly `.rule` files: an `@RULE` name, the transitions in an `@TABLE` or `@TREE` section
+//! and optional `@COLORS`. Other sections such as `@ICONS` are skipped.
+
+use std::path::{Path, PathBuf};
+
+use super::{table, tree::RuleTree};
+
+/// Where rules named in patterns are looked for as `<name>.rule`.
+pub const RULE_DIRS: [&str; 2] = ["rules", "."];
+
+/// Parse a whole `.rule` file.
+pub fn parse(s: &str) -> Result<RuleTree, String> {
+    let mut name = None;
+    let mut sections: Vec<(&str, String)> = Vec::new();
+    for line in s.lines() {
+        if let Some(header) = line.trim_start().strip_prefix('@') {
+            let (section, rest) = header
+                .split_once(char::is_whitespace)
+                .unwrap_or((header, ""));
+            if section == "RULE" {
+                name = Some(rest.trim().to_string());
+            }
+            sections.push((section, String::new()));
+        } else if let Some((_, body)) = sections.last_mut() {
+            *body += line;
+            *body += "\n";
+        }
+    }
+    let name = name
+        .filter(|name| !name.is_empty())
+        .ok_or("missing @RULE name")?;
+    let section = |wanted: &str| {
+        sections
+            .iter()
+            .find(|(section, _)| *section == wanted)
+            .map(|(_, body)| body.as_str())
+    };
+
+    let mut tree = match (section("TABLE"), section("TREE")) {
+        (Some(body), _) => table::parse(&name, body).map_err(|e| format!("@TABLE: {e}"))?,
+        (None, Some(body)) => RuleTree::parse(&name, body).map_err(|e| format!("@TREE: {e}"))?,
+        (None, None) => return Err(format!("{name} has no @TABLE or @TREE")),
+    };
+    if let Some(body) = section("COLORS") {
+        read_colors(body, &mut tree.colors).map_err(|e| format!("@COLORS: {e}"))?;
+    }
+    Ok(tree)
+}
+
+/// Apply `@COLORS` lines, `state r g b` colours one state and `r g b r g b` spreads a
+/// gradient over the states after 0.
+fn read_colors(s: &str, colors: &mut [[u8; 3]]) -> Result<(), String> {
+    for line in s.lines() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let numbers = line
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|n| !n.is_empty())
+            .map(|n| n.parse::<u32>().map_err(|_| format!("bad number `{n}`")))
+            .collect::<Result<Vec<_>, _>>()?;
+        let color = |rgb: &[u32]| -> Result<[u8; 3], String> {
+            let mut color = [0; 3];
+            for (c, &n) in color.iter_mut().zip(rgb) {
+                *c = u8::try_from(n).map_err(|_| format!("colour {n} is over 255"))?;
+            }
+            Ok(color)
+        };
+        match numbers[..] {
+            [state, ref rgb @ ..] if rgb.len() == 3 => {
+                let color = color(rgb)?;
+                *colors
+                    .get_mut(state as usize)
+                    .ok_or_else(|| format!("no state {state}"))? = color;
+            }
+            ref gradient if gradient.len() == 6 => {
+                let (from, to) = (color(&gradient[..3])?, color(&gradient[3..])?);
+                let live = colors.len().saturating_sub(2).max(1) as f32;
+                for (i, color) in colors.iter_mut().enumerate().skip(1) {
+                    let t = (i - 1) as f32 / live;
+                    *color = std::array::from_fn(|c| {
+                        (from[c] as f32 + (to[c] as f32 - from[c] as f32) * t).round() as u8
+                    });
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "expected `state r g b` or `r g b r g b`, found `{line}`"
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn load(path: &Path) -> Result<RuleTree, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    parse(&contents).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// The `.rule` file a rulestring refers to: a path ending in `.rule`, or a name such as
+/// `WireWorld` with a `<name>.rule` file in one of `RULE_DIRS`.
+pub fn find(s: &str) -> Option<PathBuf> {
+    let s = s.trim();
+    if s.ends_with(".rule") {
+        return Some(PathBuf::from(s));
+    }
+    let is_name = s.starts_with(|c: char| c.is_ascii_alphabetic())
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_".contains(c));
+    if !is_name {
+        return None;
+    }
+    RULE_DIRS
+        .iter()
+        .map(|dir| Path::new(dir).join(format!("{s}.rule")))
+        .find(|path| path.is_file())
+}
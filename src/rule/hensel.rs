@@ -0,0 +1,162 @@
+//! Isotropic non-totalistic rules in Hensel notation, `B2-a/S12` or `B3/S2-i34q`.
+
+use std::fmt;
+
+use super::RuleError;
+
+/// Letters of 0 to 4 neighbours in Golly's order, 8 - n neighbours reuse those of n.
+const LETTERS: [&str; 5] = ["", "ce", "ceaikn", "ceaiknjqry", "ceaiknjqrytwz"];
+
+/// One neighbourhood per letter of `LETTERS`, bit `i` is cell `i` of the 3x3 block in reading
+/// order so bit 4 is the middle cell.
+const NEIGHBOURHOODS: [&[u16]; 5] = [
+    &[0],
+    &[1, 2],
+    &[5, 10, 3, 40, 33, 68],
+    &[69, 42, 11, 7, 98, 13, 14, 70, 41, 97],
+    &[325, 170, 15, 45, 99, 71, 106, 102, 43, 101, 105, 78, 108],
+];
+
+/// The eight neighbours of the 3x3 block.
+const NEIGHBOURS: u16 = 0x1ef;
+
+/// Which configurations of each neighbour count give birth and survival, bit `i` of
+/// `birth[n]` set means the `i`th letter of `n` neighbours applies. Counts 0 and 8 have no
+/// letters and use bit 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Isotropic {
+    pub birth: [u16; 9],
+    pub survival: [u16; 9],
+}
+
+fn letters(n: usize) -> &'static str {
+    LETTERS[n.min(8 - n)]
+}
+
+/// Every configuration of `n` neighbours.
+fn full(n: usize) -> u16 {
+    (1 << letters(n).len().max(1)) - 1
+}
+
+/// The neighbourhood named by the `letter`th letter of `n`, complemented past 4 neighbours.
+fn neighbourhood(n: usize, letter: usize) -> u16 {
+    match n {
+        0..=4 => NEIGHBOURHOODS[n][letter],
+        _ => NEIGHBOURHOODS[8 - n][letter] ^ NEIGHBOURS,
+    }
+}
+
+/// The 8 rotations and reflections of a 3x3 block.
+fn symmetries(block: u16) -> impl Iterator<Item = u16> {
+    let map = |block: u16, f: fn(usize, usize) -> (usize, usize)| {
+        (0..9)
+            .filter(|i| block >> i & 1 == 1)
+            .map(|i| {
+                let (x, y) = f(i % 3, i / 3);
+                1 << (y * 3 + x)
+            })
+            .sum::<u16>()
+    };
+    let rotations = std::iter::successors(Some(block), move |&block| {
+        Some(map(block, |x, y| (2 - y, x)))
+    })
+    .take(4);
+    rotations.flat_map(move |block| [block, map(block, |x, y| (2 - x, y))])
+}
+
+/// Parse neighbour counts with optional letters, `2-i34q`, into masks as in `Isotropic`.
+pub fn parse_conditions(s: &str) -> Result<[u16; 9], RuleError> {
+    let mut masks = [0; 9];
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        let n = match c.to_digit(10) {
+            Some(n) if n <= 8 => n as usize,
+            _ => return Err(RuleError::Digit(c)),
+        };
+        let minus = chars.next_if_eq(&'-').is_some();
+        let mut chosen = 0;
+        while let Some(letter) = chars.next_if(char::is_ascii_lowercase) {
+            let i = letters(n)
+                .find(letter)
+                .ok_or(RuleError::Letter(letter, n as u32))?;
+            chosen |= 1 << i;
+        }
+        masks[n] |= match (minus, chosen) {
+            (false, 0) => full(n),
+            (false, chosen) => chosen,
+            (true, chosen) => full(n) & !chosen,
+        };
+    }
+    Ok(masks)
+}
+
+impl Isotropic {
+    /// Birth and survival bitmasks of a Life-like rule, if every count applies in all of its
+    /// configurations or none.
+    pub fn totalistic(&self) -> Option<(u32, u32)> {
+        let mask = |masks: &[u16; 9]| {
+            masks
+                .iter()
+                .enumerate()
+                .try_fold(0, |mask, (n, &m)| match m {
+                    0 => Some(mask),
+                    m if m == full(n) => Some(mask | 1 << n),
+                    _ => None,
+                })
+        };
+        Some((mask(&self.birth)?, mask(&self.survival)?))
+    }
+
+    /// 1 where the 3x3 block, bit 4 the middle cell as in `NEIGHBOURHOODS`, has a live
+    /// middle cell next generation.
+    pub fn table(&self) -> [u32; 512] {
+        let mut table = [0; 512];
+        for n in 0..=8 {
+            for letter in 0..letters(n).len().max(1) {
+                let conditions = [(self.birth[n], 0), (self.survival[n], 1 << 4)];
+                for (mask, middle) in conditions {
+                    if mask >> letter & 1 == 1 {
+                        for block in symmetries(neighbourhood(n, letter)) {
+                            table[(block | middle) as usize] = 1;
+                        }
+                    }
+                }
+            }
+        }
+        table
+    }
+}
+
+/// `masks` the way `parse_conditions` reads them, with the shorter of the letters that apply
+/// and the `-` letters that do not.
+pub fn display_conditions(masks: &[u16; 9]) -> impl fmt::Display + '_ {
+    Conditions(masks)
+}
+
+struct Conditions<'a>(&'a [u16; 9]);
+
+impl fmt::Display for Conditions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (n, &mask) in self.0.iter().enumerate().filter(|(_, &mask)| mask != 0) {
+            write!(f, "{n}")?;
+            if mask == full(n) {
+                continue;
+            }
+            let pick = |keep: u16| -> String {
+                letters(n)
+                    .chars()
+                    .enumerate()
+                    .filter(|(i, _)| keep >> i & 1 == 1)
+                    .map(|(_, c)| c)
+                    .collect()
+            };
+            let excluded = full(n) & !mask;
+            if excluded.count_ones() < mask.count_ones() {
+                write!(f, "-{}", pick(excluded))?;
+            } else {
+                write!(f, "{}", pick(mask))?;
+            }
+        }
+        Ok(())
+    }
+}
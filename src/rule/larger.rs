@@ -0,0 +1,170 @@
+//! Larger than Life rules in Golly's notation, `R5,C0,M1,S34..58,B34..45,NM`.
+
+use std::fmt;
+
+/// Largest neighbourhood radius, `compute.wgsl` keeps tiles of `8 + 2 * MAX_RANGE` cells.
+pub const MAX_RANGE: u32 = 10;
+
+/// Shape of a Larger than Life neighbourhood.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// The square of cells up to `range` away on both axes, `NM`.
+    Moore,
+    /// The diamond of cells up to `range` steps away, `NN`.
+    VonNeumann,
+    /// Cells whose centres are closer than `range + 1/2` to the middle one, `NC`.
+    Circular,
+}
+
+impl Neighbourhood {
+    /// How far the neighbourhood reaches left and right of the middle column in the row `dy`
+    /// away from the middle.
+    pub fn half_width(self, range: u32, dy: i32) -> i32 {
+        let range = range as i32;
+        match self {
+            Self::Moore => range,
+            Self::VonNeumann => range - dy.abs(),
+            Self::Circular => {
+                let limit = range * range + range - dy * dy;
+                (0..=range).rev().find(|w| w * w <= limit).unwrap_or(0)
+            }
+        }
+    }
+
+    /// Number of cells in the neighbourhood, the middle one included.
+    pub fn size(self, range: u32) -> u32 {
+        let range = range as i32;
+        (-range..=range)
+            .map(|dy| 2 * self.half_width(range as u32, dy) as u32 + 1)
+            .sum()
+    }
+
+    fn letter(self) -> char {
+        match self {
+            Self::Moore => 'M',
+            Self::VonNeumann => 'N',
+            Self::Circular => 'C',
+        }
+    }
+}
+
+/// The neighbourhood and the inclusive count ranges of a Larger than Life rule, the state
+/// count lives in `Rule::states`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LargerThanLife {
+    pub range: u32,
+    pub neighbourhood: Neighbourhood,
+    /// Whether a live cell counts itself.
+    pub middle: bool,
+    pub survival: (u32, u32),
+    pub birth: (u32, u32),
+}
+
+impl LargerThanLife {
+    /// Parse the rule and its state count, `None` if `s` is not in Larger than Life notation.
+    pub fn parse(s: &str) -> Option<Result<(Self, u32), String>> {
+        let s = s.trim();
+        let is_larger = s.len() > 1
+            && s.starts_with(['R', 'r'])
+            && s[1..].starts_with(|c: char| c.is_ascii_digit());
+        is_larger.then(|| Self::parse_fields(s))
+    }
+
+    fn parse_fields(s: &str) -> Result<(Self, u32), String> {
+        let mut fields = s.split(',').map(str::trim);
+        let mut field = |letter: char| {
+            fields
+                .next()
+                .and_then(|field| field.strip_prefix([letter, letter.to_ascii_lowercase()]))
+                .ok_or_else(|| format!("expected `{letter}` field in `{s}`"))
+        };
+        let number = |field: &str| {
+            field
+                .parse::<u32>()
+                .map_err(|_| format!("bad number `{field}` in `{s}`"))
+        };
+        let counts = |field: &str| match field.split_once("..") {
+            Some((min, max)) => Ok((number(min)?, number(max)?)),
+            None => number(field).map(|n| (n, n)),
+        };
+
+        let range = number(field('R')?)?;
+        let states = match number(field('C')?)? {
+            0 | 1 => 2,
+            states => states,
+        };
+        let middle = match field('M')? {
+            "0" => false,
+            "1" => true,
+            m => return Err(format!("expected M0 or M1, found `M{m}`")),
+        };
+        let survival = counts(field('S')?)?;
+        let birth = counts(field('B')?)?;
+        let neighbourhood = match fields.next() {
+            Some("NM" | "nm") => Neighbourhood::Moore,
+            Some("NN" | "nn") => Neighbourhood::VonNeumann,
+            Some("NC" | "nc") => Neighbourhood::Circular,
+            Some(n) => {
+                return Err(format!(
+                    "unknown neighbourhood `{n}`, expected NM, NN or NC"
+                ))
+            }
+            // Golly's default.
+            None => Neighbourhood::Moore,
+        };
+        if fields.next().is_some() {
+            return Err(format!(
+                "unexpected fields after the neighbourhood in `{s}`"
+            ));
+        }
+
+        if !(1..=MAX_RANGE).contains(&range) {
+            return Err(format!("range must be 1 to {MAX_RANGE}, found {range}"));
+        }
+        if !(2..=super::Rule::MAX_STATES).contains(&states) {
+            return Err(format!("expected 2 to 256 states, found {states}"));
+        }
+        let size = neighbourhood.size(range);
+        for (name, (min, max)) in [('S', survival), ('B', birth)] {
+            if min > max || max > size {
+                return Err(format!(
+                    "{name}{min}..{max} is not a range in 0..={size}, the neighbourhood size"
+                ));
+            }
+        }
+
+        let rule = Self {
+            range,
+            neighbourhood,
+            middle,
+            survival,
+            birth,
+        };
+        Ok((rule, states))
+    }
+
+    /// The rule in Golly's notation with `states` as the `C` field.
+    pub fn display(&self, states: u32) -> impl fmt::Display + '_ {
+        Notation(self, states)
+    }
+}
+
+struct Notation<'a>(&'a LargerThanLife, u32);
+
+impl fmt::Display for Notation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Notation(rule, states) = self;
+        let states = if *states == 2 { 0 } else { *states };
+        write!(
+            f,
+            "R{},C{states},M{},S{}..{},B{}..{},N{}",
+            rule.range,
+            rule.middle as u8,
+            rule.survival.0,
+            rule.survival.1,
+            rule.birth.0,
+            rule.birth.1,
+            rule.neighbourhood.letter()
+        )
+    }
+}
@@ -0,0 +1,353 @@
+//! Golly rule tables, the `@TABLE` section of `.rule` files, compiled to a `RuleTree`.
+
+use std::collections::HashMap;
+
+use super::tree::{RuleTree, TreeBuilder};
+
+/// Where the levels of a tree branch in the cells of a transition, which lists the middle
+/// cell, then the neighbours clockwise from north.
+const MOORE_ORDER: [usize; 9] = [8, 2, 6, 4, 1, 7, 3, 5, 0];
+const VON_NEUMANN_ORDER: [usize; 5] = [1, 4, 2, 3, 0];
+
+/// A set of states, bit `s` set if state `s` is in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct States([u64; 4]);
+
+impl States {
+    const EMPTY: Self = Self([0; 4]);
+
+    fn single(state: u32) -> Self {
+        let mut states = Self::EMPTY;
+        states.0[state as usize / 64] |= 1 << (state % 64);
+        states
+    }
+
+    fn contains(&self, state: u32) -> bool {
+        self.0[state as usize / 64] >> (state % 64) & 1 == 1
+    }
+
+    fn union(self, other: Self) -> Self {
+        Self(std::array::from_fn(|i| self.0[i] | other.0[i]))
+    }
+
+    fn iter(self) -> impl Iterator<Item = u32> {
+        (0..256).filter(move |&state| self.contains(state))
+    }
+}
+
+/// A transition with its variables resolved, the middle cell, the neighbours clockwise from
+/// north and the new state.
+struct Transition {
+    cells: Vec<States>,
+    output: u32,
+}
+
+#[derive(Clone, Copy)]
+enum Symmetry {
+    None,
+    Rotate4,
+    Rotate8,
+    ReflectHorizontal,
+    Rotate4Reflect,
+    Rotate8Reflect,
+    Permute,
+}
+
+impl Symmetry {
+    fn parse(s: &str) -> Result<Self, String> {
+        Ok(match s {
+            "none" => Self::None,
+            "rotate4" => Self::Rotate4,
+            "rotate8" => Self::Rotate8,
+            "reflect_horizontal" => Self::ReflectHorizontal,
+            "rotate4reflect" => Self::Rotate4Reflect,
+            "rotate8reflect" => Self::Rotate8Reflect,
+            "permute" => Self::Permute,
+            s => return Err(format!("unknown symmetries `{s}`")),
+        })
+    }
+
+    /// Orders of the `n` neighbours this symmetry makes equivalent, entry `i` tells which
+    /// neighbour goes in place `i`. Empty for `Permute`, which allows every order.
+    fn orders(self, n: usize) -> Result<Vec<Vec<usize>>, String> {
+        let (step, reflect) = match self {
+            Self::None => (n, false),
+            Self::Rotate4 => (n / 4, false),
+            Self::Rotate8 => (1, false),
+            Self::ReflectHorizontal => (n, true),
+            Self::Rotate4Reflect => (n / 4, true),
+            Self::Rotate8Reflect => (1, true),
+            Self::Permute => return Ok(Vec::new()),
+        };
+        if step == 1 && n == 4 && matches!(self, Self::Rotate8 | Self::Rotate8Reflect) {
+            return Err("rotate8 symmetries need the Moore neighborhood".to_string());
+        }
+        let mut orders = Vec::new();
+        for rotation in (0..n).step_by(step) {
+            orders.push((0..n).map(|i| (i + rotation) % n).collect());
+            if reflect {
+                orders.push((0..n).map(|i| (n - i + rotation) % n).collect());
+            }
+        }
+        Ok(orders)
+    }
+}
+
+/// Rearrange `items` into the next order in lexicographic order, false after the last.
+fn next_permutation(items: &mut [usize]) -> bool {
+    let Some(i) = (1..items.len()).rev().find(|&i| items[i - 1] < items[i]) else {
+        return false;
+    };
+    let j = (i..items.len())
+        .rev()
+        .find(|&j| items[j] > items[i - 1])
+        .unwrap();
+    items.swap(i - 1, j);
+    items[i..].reverse();
+    true
+}
+
+/// Parse the body of an `@TABLE` section.
+pub fn parse(name: &str, s: &str) -> Result<RuleTree, String> {
+    let mut states = None;
+    let mut neighbours = None;
+    let mut symmetry = Symmetry::None;
+    let mut variables: HashMap<&str, States> = HashMap::new();
+    let mut transitions = Vec::new();
+
+    for line in s.lines() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim();
+            match key.trim() {
+                "n_states" => {
+                    let count = value
+                        .parse()
+                        .ok()
+                        .filter(|n| (2..=super::Rule::MAX_STATES).contains(n))
+                        .ok_or_else(|| format!("expected 2 to 256 states, found `{value}`"))?;
+                    states = Some(count);
+                }
+                "neighborhood" => {
+                    neighbours = Some(match value {
+                        "Moore" => 8,
+                        "vonNeumann" => 4,
+                        n => return Err(format!("unsupported neighborhood `{n}`")),
+                    })
+                }
+                "symmetries" => symmetry = Symmetry::parse(value)?,
+                key => return Err(format!("unknown `{key}` in @TABLE")),
+            }
+            continue;
+        }
+
+        let (Some(states), Some(neighbours)) = (states, neighbours) else {
+            return Err("n_states and neighborhood must come first in @TABLE".to_string());
+        };
+        let state = |token: &str| -> Result<States, String> {
+            if let Some(&states) = variables.get(token) {
+                return Ok(states);
+            }
+            match token.parse() {
+                Ok(state) if state < states => Ok(States::single(state)),
+                _ => Err(format!("`{token}` is neither a state nor a variable")),
+            }
+        };
+
+        if let Some(var) = line.strip_prefix("var ") {
+            let (var, list) = var
+                .split_once('=')
+                .ok_or_else(|| format!("expected `var name={{..}}`, found `{line}`"))?;
+            let list = list
+                .trim()
+                .strip_prefix('{')
+                .and_then(|list| list.strip_suffix('}'))
+                .ok_or_else(|| format!("expected `{{..}}` after `var {}=`", var.trim()))?;
+            let set = list.split(',').try_fold(States::EMPTY, |set, token| {
+                Ok::<_, String>(set.union(state(token.trim())?))
+            })?;
+            variables.insert(var.trim(), set);
+            continue;
+        }
+
+        // All on one line without commas when every state is a single digit, `0123`.
+        let tokens: Vec<&str> = match line.contains(',') {
+            true => line.split(',').map(str::trim).collect(),
+            false => line
+                .char_indices()
+                .map(|(i, c)| &line[i..i + c.len_utf8()])
+                .collect(),
+        };
+        if tokens.len() != neighbours + 2 {
+            return Err(format!("expected {} states in `{line}`", neighbours + 2));
+        }
+        expand(
+            &tokens,
+            &variables,
+            states,
+            symmetry,
+            neighbours,
+            &mut transitions,
+        )
+        .map_err(|e| format!("{e} in `{line}`"))?;
+    }
+
+    let (Some(states), Some(neighbours)) = (states, neighbours) else {
+        return Err("missing n_states or neighborhood in @TABLE".to_string());
+    };
+    let order: &[usize] = match neighbours {
+        8 => &MOORE_ORDER,
+        _ => &VON_NEUMANN_ORDER,
+    };
+    let mut compiler = Compiler {
+        transitions: &transitions,
+        order,
+        states,
+        builder: TreeBuilder::new(states),
+        memo: HashMap::new(),
+    };
+    let root = compiler.build(0, (0..transitions.len()).collect());
+    Ok(compiler.builder.finish(name, neighbours as u32, root))
+}
+
+/// Add the transitions a table line stands for to `transitions`: one per value of the
+/// variables it uses more than once, which are bound to the same state, and per equivalent
+/// order of the neighbours.
+fn expand(
+    tokens: &[&str],
+    variables: &HashMap<&str, States>,
+    states: u32,
+    symmetry: Symmetry,
+    neighbours: usize,
+    transitions: &mut Vec<Transition>,
+) -> Result<(), String> {
+    let (output, inputs) = tokens.split_last().unwrap();
+    let bound: Vec<&str> = variables
+        .keys()
+        .copied()
+        .filter(|var| tokens.iter().filter(|token| *token == var).count() > 1)
+        .collect();
+    if variables.contains_key(output) && !bound.contains(output) {
+        return Err(format!("output `{output}` is not bound to an input"));
+    }
+    let output_state = |values: &HashMap<&str, u32>| -> Result<u32, String> {
+        match values.get(output) {
+            Some(&value) => Ok(value),
+            None => output
+                .parse()
+                .ok()
+                .filter(|&state| state < states)
+                .ok_or_else(|| format!("bad output `{output}`")),
+        }
+    };
+
+    // Every combination of values of the bound variables.
+    let mut assignments = vec![HashMap::new()];
+    for var in &bound {
+        assignments = assignments
+            .into_iter()
+            .flat_map(|values: HashMap<&str, u32>| {
+                variables[var].iter().map(move |value| {
+                    let mut values = values.clone();
+                    values.insert(*var, value);
+                    values
+                })
+            })
+            .collect();
+    }
+
+    let orders = symmetry.orders(neighbours)?;
+    for values in assignments {
+        let cells = inputs
+            .iter()
+            .map(|token| match (values.get(token), variables.get(token)) {
+                (Some(&value), _) => Ok(States::single(value)),
+                (None, Some(&set)) => Ok(set),
+                (None, None) => match token.parse() {
+                    Ok(state) if state < states => Ok(States::single(state)),
+                    _ => Err(format!("`{token}` is neither a state nor a variable")),
+                },
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let output = output_state(&values)?;
+        let (middle, around) = cells.split_first().unwrap();
+
+        let mut arrangements: Vec<Vec<States>> = Vec::new();
+        if orders.is_empty() {
+            // Every distinct order of the neighbours, permuting indices into the distinct sets.
+            let mut distinct: Vec<States> = Vec::new();
+            let mut indices: Vec<usize> = around
+                .iter()
+                .map(|set| match distinct.iter().position(|d| d == set) {
+                    Some(i) => i,
+                    None => {
+                        distinct.push(*set);
+                        distinct.len() - 1
+                    }
+                })
+                .collect();
+            indices.sort_unstable();
+            loop {
+                arrangements.push(indices.iter().map(|&i| distinct[i]).collect());
+                if !next_permutation(&mut indices) {
+                    break;
+                }
+            }
+        } else {
+            for order in &orders {
+                let arrangement: Vec<States> = order.iter().map(|&i| around[i]).collect();
+                if !arrangements.contains(&arrangement) {
+                    arrangements.push(arrangement);
+                }
+            }
+        }
+        for around in arrangements {
+            let mut cells = vec![*middle];
+            cells.extend(around);
+            transitions.push(Transition { cells, output });
+        }
+    }
+    Ok(())
+}
+
+/// Turns the transitions into tree nodes, the first transition a cell matches wins and cells
+/// matching none keep their state.
+struct Compiler<'a> {
+    transitions: &'a [Transition],
+    order: &'a [usize],
+    states: u32,
+    builder: TreeBuilder,
+    /// Nodes already built for the transitions still matching at a depth.
+    memo: HashMap<(usize, Vec<usize>), u32>,
+}
+
+impl Compiler<'_> {
+    fn build(&mut self, depth: usize, candidates: Vec<usize>) -> u32 {
+        if let Some(&node) = self.memo.get(&(depth, candidates.clone())) {
+            return node;
+        }
+        let cell = self.order[depth];
+        let last = depth + 1 == self.order.len();
+        let children = (0..self.states)
+            .map(|state| {
+                let matching: Vec<usize> = candidates
+                    .iter()
+                    .copied()
+                    .filter(|&t| self.transitions[t].cells[cell].contains(state))
+                    .collect();
+                match last {
+                    true => matching
+                        .first()
+                        .map_or(state, |&t| self.transitions[t].output),
+                    false => self.build(depth + 1, matching),
+                }
+            })
+            .collect();
+        let node = self.builder.node(self.order.len() - depth, children);
+        self.memo.insert((depth, candidates), node);
+        node
+    }
+}
@@ -0,0 +1,205 @@
+//! Golly rule trees, the transition lookup of `.rule` files that `@TABLE` sections are
+//! compiled to as well.
+
+use std::{collections::HashMap, sync::Mutex};
+
+/// Cells a rule tree reads, in the order Golly's trees branch on them, the middle cell last.
+const MOORE: [(i32, i32); 9] = [
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+    (0, -1),
+    (-1, 0),
+    (1, 0),
+    (0, 1),
+    (0, 0),
+];
+const VON_NEUMANN: [(i32, i32); 5] = [(0, -1), (-1, 0), (1, 0), (0, 1), (0, 0)];
+
+/// A decision tree branching on one cell's state per level, `nodes` holds `states` children
+/// per node. Children of the last level are the new state of the middle cell, the others are
+/// where their child node starts in `nodes`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleTree {
+    pub name: String,
+    pub states: u32,
+    /// 8 for the Moore neighbourhood, 4 for von Neumann's.
+    pub neighbours: u32,
+    pub nodes: Vec<u32>,
+    /// Where the root node starts in `nodes`.
+    pub root: u32,
+    /// Colour of each state, from `@COLORS` or Golly's default red to yellow.
+    pub colors: Vec<[u8; 3]>,
+}
+
+impl RuleTree {
+    /// The tree kept for the rest of the process, which lets `Rule` stay `Copy`. Each distinct
+    /// tree is kept once however often its file is loaded.
+    pub fn intern(self) -> &'static RuleTree {
+        static TREES: Mutex<Vec<&'static RuleTree>> = Mutex::new(Vec::new());
+        let mut trees = TREES.lock().unwrap();
+        if let Some(&tree) = trees.iter().find(|&&tree| *tree == self) {
+            return tree;
+        }
+        let tree = Box::leak(Box::new(self));
+        trees.push(tree);
+        tree
+    }
+
+    /// Where the cells are relative to the middle one, in the order the levels branch on them.
+    pub fn offsets(&self) -> &'static [(i32, i32)] {
+        match self.neighbours {
+            8 => &MOORE,
+            _ => &VON_NEUMANN,
+        }
+    }
+
+    /// Next state of a cell, `state` gives the state of the cell at an offset from it. States
+    /// the tree doesn't have are read as 0.
+    pub fn next(&self, state: impl Fn((i32, i32)) -> u32) -> u32 {
+        self.offsets().iter().fold(self.root, |node, &offset| {
+            let state = state(offset);
+            let state = if state < self.states { state } else { 0 };
+            self.nodes[(node + state) as usize]
+        })
+    }
+
+    /// Golly's colours for rules without `@COLORS`, a gradient from red to yellow.
+    pub fn default_colors(states: u32) -> Vec<[u8; 3]> {
+        (0..states)
+            .map(|state| match state {
+                0 => [48, 48, 48],
+                state => {
+                    let t = (state - 1) as f32 / (states - 2).max(1) as f32;
+                    [255, (t * 255.0).round() as u8, 0]
+                }
+            })
+            .collect()
+    }
+
+    /// Parse the body of an `@TREE` section.
+    pub fn parse(name: &str, s: &str) -> Result<Self, String> {
+        let mut header = HashMap::new();
+        let mut lines = s
+            .lines()
+            .map(|line| line.split('#').next().unwrap().trim())
+            .filter(|line| !line.is_empty())
+            .peekable();
+        while let Some((key, value)) = lines.peek().and_then(|line| line.split_once('=')) {
+            let value: u32 = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("bad number `{}` for `{}`", value.trim(), key.trim()))?;
+            header.insert(key.trim(), value);
+            lines.next();
+        }
+        let field = |key: &str| {
+            header
+                .get(key)
+                .copied()
+                .ok_or_else(|| format!("missing `{key}` in @TREE"))
+        };
+        let states = field("num_states")?;
+        let neighbours = field("num_neighbors")?;
+        let count = field("num_nodes")?;
+        if !(2..=super::Rule::MAX_STATES).contains(&states) {
+            return Err(format!("expected 2 to 256 states, found {states}"));
+        }
+        if neighbours != 4 && neighbours != 8 {
+            return Err(format!("expected 4 or 8 neighbors, found {neighbours}"));
+        }
+
+        let mut levels = Vec::new();
+        let mut nodes = Vec::new();
+        for line in lines {
+            let numbers = line
+                .split_whitespace()
+                .map(|n| {
+                    n.parse::<u32>()
+                        .map_err(|_| format!("bad number `{n}` in @TREE"))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let (&level, children) = numbers.split_first().unwrap();
+            if !(1..=neighbours + 1).contains(&level) {
+                return Err(format!("bad level {level} in @TREE"));
+            }
+            if children.len() != states as usize {
+                return Err(format!("expected {states} children, found `{line}`"));
+            }
+            for &child in children {
+                let valid = match level {
+                    1 => child < states,
+                    _ => levels.get(child as usize) == Some(&(level - 1)),
+                };
+                if !valid {
+                    return Err(format!("bad child {child} of a level {level} node"));
+                }
+                nodes.push(match level {
+                    1 => child,
+                    _ => child * states,
+                });
+            }
+            levels.push(level);
+        }
+        if levels.len() != count as usize {
+            return Err(format!("expected {count} nodes, found {}", levels.len()));
+        }
+        if levels.last() != Some(&(neighbours + 1)) {
+            return Err(format!(
+                "the last node must be the root at level {}",
+                neighbours + 1
+            ));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            states,
+            neighbours,
+            root: (levels.len() as u32 - 1) * states,
+            nodes,
+            colors: Self::default_colors(states),
+        })
+    }
+}
+
+/// Builds the nodes of a tree bottom up, sharing identical nodes.
+pub struct TreeBuilder {
+    states: u32,
+    nodes: Vec<u32>,
+    ids: HashMap<(usize, Vec<u32>), u32>,
+}
+
+impl TreeBuilder {
+    pub fn new(states: u32) -> Self {
+        Self {
+            states,
+            nodes: Vec::new(),
+            ids: HashMap::new(),
+        }
+    }
+
+    /// Where the node with `children` on `level` starts, added if it is new.
+    pub fn node(&mut self, level: usize, children: Vec<u32>) -> u32 {
+        debug_assert_eq!(children.len(), self.states as usize);
+        let next = self.nodes.len() as u32;
+        *self
+            .ids
+            .entry((level, children))
+            .or_insert_with_key(|(_, children)| {
+                self.nodes.extend(children);
+                next
+            })
+    }
+
+    pub fn finish(self, name: &str, neighbours: u32, root: u32) -> RuleTree {
+        RuleTree {
+            name: name.to_string(),
+            states: self.states,
+            neighbours,
+            nodes: self.nodes,
+            root,
+            colors: RuleTree::default_colors(self.states),
+        }
+    }
+}
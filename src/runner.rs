@@ -1,12 +1,329 @@
-use winit::{event_loop::EventLoop, window::WindowBuilder};
+use std::{error::Error, path::Path};
 
-use crate::core::Core;
+use winit::{dpi::PhysicalSize, event_loop::EventLoop, window::WindowBuilder};
+
+use crate::{
+    cli::Args,
+    core::Core,
+    cpu::CpuSimulator,
+    gpu::GpuSimulator,
+    grid::Grid,
+    hashlife::HashLife,
+    packed::PackedSimulator,
+    pattern::{macrocell::Macrocell, Format, Pattern, MAX_CELLS},
+    rule::Rule,
+    simulator::{Simulator, UnboundedSimulator},
+    soup::{self, Symmetry},
+    sparse::SparseLife,
+    tiling::Tiling,
+    topology::{BoundedGrid, Topology},
+};
+
+const DEFAULT_GRID_SIZE: u32 = 40;
+
+pub fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let seed = args.seed.unwrap_or_else(rand::random);
+    if args.hashlife {
+        let (life, _) = initial_unbounded::<HashLife>(&args, seed)?;
+        return run_headless_unbounded(&args, life);
+    }
+    let (grid, rule, topology, sparse) = match args.infinite {
+        true => {
+            let (life, grid) = initial_unbounded::<SparseLife>(&args, seed)?;
+            (grid, life.rule, Topology::default(), Some(life))
+        }
+        false => {
+            let (grid, rule, topology) = initial_grid(&args, seed)?;
+            let size = (grid.width, grid.height);
+            rule.tiling.check_topology(topology, size)?;
+            (grid, rule, topology, None)
+        }
+    };
+    if args.headless {
+        return match sparse {
+            Some(life) => run_headless_unbounded(&args, life),
+            None => run_headless(&args, grid, rule, topology),
+        };
+    }
 
-pub fn run() {
     let event_loop = EventLoop::new();
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
+    let window = WindowBuilder::new()
+        .with_title("game of life")
+        .with_inner_size(PhysicalSize::new(args.window_size.0, args.window_size.1))
+        .build(&event_loop)?;
     pollster::block_on(async {
-        let core = Core::new(&event_loop, &window).await;
+        let mut core = Core::new(&event_loop, &window, grid, rule).await;
+        core.density = args.density;
+        core.seed = seed;
+        core.simulator.set_topology(topology);
+        core.scheduler.set_rate(args.rate);
+        core.scheduler.paused = args.paused;
+        if let Some(life) = sparse {
+            core.use_sparse(life);
+        }
         core.block_loop(event_loop, window);
     });
+    Ok(())
+}
+
+fn initial_grid(args: &Args, seed: u64) -> Result<(Grid, Rule, Topology), Box<dyn Error>> {
+    let pattern = match &args.pattern {
+        Some(path) => {
+            let contents =
+                std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+            Some(
+                Pattern::parse_detect(path, &contents)
+                    .map_err(|e| format!("{}:{e}", path.display()))?,
+            )
+        }
+        None => None,
+    };
+
+    let (rule, bounds) = match (args.rule, &pattern) {
+        (Some(rule), _) => rule,
+        (None, Some(pattern)) => (pattern.rule.unwrap_or_default(), pattern.bounds),
+        (None, None) => (Rule::default(), None),
+    };
+    let topology = match (args.wrap, bounds) {
+        (Some(_), Some(bounds)) => {
+            return Err(format!("--wrap conflicts with the bounded grid `{bounds}`").into())
+        }
+        (Some(wrap), None) => wrap.into(),
+        (None, Some(bounds)) => bounds.topology,
+        (None, None) => Topology::default(),
+    };
+
+    // A bounded grid fixes the size of its non-zero dimensions.
+    let bound = |given: Option<u32>, bound: Option<u32>, name: &str| match (given, bound) {
+        (Some(given), Some(bound)) if bound != 0 && given != bound => Err(format!(
+            "--{name} {given} conflicts with the bounded grid {name} {bound}"
+        )),
+        (None, Some(bound)) if bound != 0 => Ok(Some(bound)),
+        _ => Ok(given),
+    };
+    let width = bound(args.width, bounds.map(|b| b.width), "width")?;
+    let height = bound(args.height, bounds.map(|b| b.height), "height")?;
+
+    let Some(pattern) = pattern else {
+        let width = width.unwrap_or(DEFAULT_GRID_SIZE);
+        let height = height.unwrap_or(DEFAULT_GRID_SIZE);
+        return Ok((soup(args, width, height, seed), rule, topology));
+    };
+
+    let width = width.unwrap_or(pattern.grid.width.max(DEFAULT_GRID_SIZE));
+    let height = height.unwrap_or(pattern.grid.height.max(DEFAULT_GRID_SIZE));
+    let mut grid = Grid::empty(width, height);
+    pattern.place(
+        &mut grid,
+        width.saturating_sub(pattern.grid.width) / 2,
+        height.saturating_sub(pattern.grid.height) / 2,
+    );
+    Ok((grid, rule, topology))
+}
+
+/// The unbounded plane `--infinite` and `--hashlife` start from, with the grid that sizes
+/// later soups. Macrocell files are read straight into the plane, they can be far larger than
+/// any grid.
+fn initial_unbounded<L: UnboundedSimulator>(
+    args: &Args,
+    seed: u64,
+) -> Result<(L, Grid), Box<dyn Error>> {
+    if let Some(macrocell) = read_macrocell(args)? {
+        let (rule, bounds) = args
+            .rule
+            .unwrap_or((macrocell.rule.unwrap_or_default(), macrocell.bounds));
+        check_unbounded(rule, bounds.map_or(Topology::default(), |b| b.topology))?;
+        // SparseLife stores every live cell, HashLife only the distinct nodes.
+        let population = macrocell.population();
+        if !args.hashlife && population > MAX_CELLS {
+            return Err(format!(
+                "{population} live cells are too many for --infinite, step them with --hashlife"
+            )
+            .into());
+        }
+        let width = args.width.unwrap_or(DEFAULT_GRID_SIZE);
+        let height = args.height.unwrap_or(DEFAULT_GRID_SIZE);
+        return Ok((
+            L::from_macrocell(&macrocell, rule),
+            Grid::empty(width, height),
+        ));
+    }
+
+    let (grid, rule, topology) = initial_grid(args, seed)?;
+    check_unbounded(rule, topology)?;
+    // Centred on the origin, like the grid is on screen.
+    let origin = (-(grid.width as i64) / 2, -(grid.height as i64) / 2);
+    Ok((L::from_grid(&grid, rule, origin), grid))
+}
+
+/// The `--pattern` file as a tree, if it is a macrocell file.
+fn read_macrocell(args: &Args) -> Result<Option<Macrocell>, Box<dyn Error>> {
+    let Some(path) = &args.pattern else {
+        return Ok(None);
+    };
+    let contents = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    match Format::detect(path, &contents) {
+        Some(Format::Macrocell) => Ok(Some(
+            Macrocell::parse(&contents).map_err(|e| format!("{}:{e}", path.display()))?,
+        )),
+        _ => Ok(None),
+    }
+}
+
+fn soup(args: &Args, width: u32, height: u32, seed: u64) -> Grid {
+    log::info!("random soup seed {seed}");
+    if args.soup_size.is_none() && args.symmetry == Symmetry::C1 {
+        return Grid::random(width, height, args.density, seed);
+    }
+
+    let size = args.soup_size.unwrap_or(width.min(height));
+    let mut grid = Grid::empty(width, height);
+    let origin = (
+        width.saturating_sub(size) / 2,
+        height.saturating_sub(size) / 2,
+    );
+    match args.symmetry {
+        Symmetry::C1 => soup::fill_rect(&mut grid, origin, (size, size), args.density, seed),
+        symmetry => Pattern::new(soup::symmetric(size, args.density, symmetry, seed))
+            .place(&mut grid, origin.0, origin.1),
+    }
+    grid
+}
+
+fn run_headless(
+    args: &Args,
+    grid: Grid,
+    rule: Rule,
+    topology: Topology,
+) -> Result<(), Box<dyn Error>> {
+    let gpu = match args.cpu {
+        true => None,
+        false => pollster::block_on(GpuSimulator::headless(&grid, rule)),
+    };
+    let mut simulator: Box<dyn Simulator> = match gpu {
+        Some(gpu) => Box::new(gpu),
+        None => {
+            if !args.cpu {
+                log::warn!("no GPU adapter, stepping on the CPU");
+            }
+            if !rule.is_life_like() {
+                // The packed backend only counts live neighbours on the 3x3 neighbourhood.
+                Box::new(CpuSimulator::new(grid, rule))
+            } else {
+                let mut cpu = PackedSimulator::new(grid, rule);
+                cpu.threads = args.threads.unwrap_or(cpu.threads);
+                Box::new(cpu)
+            }
+        }
+    };
+    simulator.set_topology(topology);
+    simulator.step_n(args.generations);
+
+    let grid = simulator.read_cells();
+    let bounds = BoundedGrid::new(topology, (grid.width, grid.height));
+    let mut pattern = Pattern::bounding_box(&grid)
+        .map_or_else(|| Pattern::new(Grid::empty(0, 0)), |(pattern, _)| pattern);
+    pattern.bounds = Some(bounds);
+    write_output(args, Some(pattern), rule, simulator.generation())
+}
+
+/// Step `--generations` and write the result, macrocell output straight from the plane.
+fn run_headless_unbounded<L: UnboundedSimulator>(
+    args: &Args,
+    mut life: L,
+) -> Result<(), Box<dyn Error>> {
+    life.step_n(args.generations.into());
+
+    let output = args.output.as_deref().unwrap();
+    if output_format(output) == Format::Macrocell {
+        let mut macrocell = life.to_macrocell();
+        macrocell
+            .comments
+            .push(format!("Generation {}", life.generation()));
+        std::fs::write(output, macrocell.write())
+            .map_err(|e| format!("{}: {e}", output.display()))?;
+        return Ok(());
+    }
+    if let Some(((min_x, min_y), (max_x, max_y))) = life.bounding_box() {
+        let size = |min: i64, max: i64| max.abs_diff(min).saturating_add(1);
+        let (width, height) = (size(min_x, max_x), size(min_y, max_y));
+        if width
+            .checked_mul(height)
+            .is_none_or(|cells| cells > MAX_CELLS)
+        {
+            return Err(format!(
+                "the final {width} x {height} pattern only fits a macrocell `.mc` output"
+            )
+            .into());
+        }
+    }
+    let pattern = life.to_grid().map(|(grid, _)| Pattern::new(grid));
+    write_output(args, pattern, life.rule(), life.generation())
+}
+
+/// Errors if `rule` or `topology` cannot be used on the unbounded plane.
+fn check_unbounded(rule: Rule, topology: Topology) -> Result<(), Box<dyn Error>> {
+    if let Some(error) = unbounded_error(rule) {
+        return Err(error.into());
+    }
+    if topology != Topology::default() {
+        return Err("a bounded grid rule conflicts with the unbounded plane".into());
+    }
+    Ok(())
+}
+
+/// Why `rule` cannot run on the infinite plane, if it cannot.
+fn unbounded_error(rule: Rule) -> Option<String> {
+    let reason = if rule.tree.is_some() {
+        "is from a .rule file"
+    } else if rule.larger.is_some() {
+        "is a Larger than Life rule"
+    } else if rule.isotropic.is_some() {
+        "is an isotropic non-totalistic rule"
+    } else if rule.tiling != Tiling::Square {
+        "is not on square cells"
+    } else if rule.states > 2 {
+        "is a Generations rule"
+    } else if rule.birth & 1 == 1 {
+        "has B0"
+    } else {
+        return None;
+    };
+    Some(format!(
+        "{rule} {reason} and cannot run on an infinite plane"
+    ))
+}
+
+/// Write the final generation, already cropped to its live cells, to `--output`.
+fn write_output(
+    args: &Args,
+    pattern: Option<Pattern>,
+    rule: Rule,
+    generation: u64,
+) -> Result<(), Box<dyn Error>> {
+    let mut pattern = pattern.unwrap_or_else(|| Pattern::new(Grid::empty(0, 0)));
+    pattern.rule = Some(rule);
+    pattern.comments.push(format!("Generation {generation}"));
+
+    let output = args.output.as_deref().unwrap();
+    let format = output_format(output);
+    if format.two_state() && pattern.grid.cell_arr.iter().any(|&state| state > 1) {
+        return Err(format!(
+            "{}: {format:?} files only hold live and dead cells, write the states of {rule} \
+             to an `.rle` or `.mc` file",
+            output.display()
+        )
+        .into());
+    }
+    std::fs::write(output, pattern.write(format))
+        .map_err(|e| format!("{}: {e}", output.display()))?;
+    Ok(())
+}
+
+fn output_format(path: &Path) -> Format {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    match extension.to_ascii_lowercase().as_str() {
+        "lif" | "life" => Format::Life106,
+        _ => Format::from_extension(path).unwrap_or(Format::Rle),
+    }
 }
@@ -0,0 +1,96 @@
+use std::time::Instant;
+
+/// How many generations to advance per second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rate {
+    /// Generations per second, may be fractional.
+    PerSecond(f64),
+    /// A fixed batch of generations every frame.
+    Unlimited,
+}
+
+impl Rate {
+    const MIN: f64 = 0.125;
+    const MAX: f64 = 512.0;
+
+    /// Twice as fast, `Unlimited` past `MAX`.
+    pub fn faster(self) -> Rate {
+        match self {
+            Rate::PerSecond(rate) if rate * 2.0 <= Self::MAX => Rate::PerSecond(rate * 2.0),
+            _ => Rate::Unlimited,
+        }
+    }
+
+    /// Half as fast, never below `MIN`.
+    pub fn slower(self) -> Rate {
+        match self {
+            Rate::PerSecond(rate) => Rate::PerSecond((rate / 2.0).max(Self::MIN)),
+            Rate::Unlimited => Rate::PerSecond(Self::MAX),
+        }
+    }
+}
+
+impl std::fmt::Display for Rate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rate::PerSecond(rate) => write!(f, "{rate} gen/s"),
+            Rate::Unlimited => write!(f, "unlimited"),
+        }
+    }
+}
+
+/// Turns elapsed wall-clock time into a whole number of generations to step.
+pub struct Scheduler {
+    pub rate: Rate,
+    /// No generations are due while paused.
+    pub paused: bool,
+    /// Generations stepped per frame with `Rate::Unlimited`.
+    pub batch: u32,
+    /// Most generations a single `tick` may return, backlog beyond it is dropped.
+    pub max_catch_up: u32,
+    last_tick: Instant,
+    /// Fraction of a generation carried over to the next tick.
+    pending: f64,
+}
+
+impl Scheduler {
+    pub fn new(rate: Rate) -> Self {
+        Self {
+            rate,
+            paused: false,
+            batch: 16,
+            max_catch_up: 1024,
+            last_tick: Instant::now(),
+            pending: 0.0,
+        }
+    }
+
+    pub fn set_rate(&mut self, rate: Rate) {
+        self.rate = rate;
+        self.pending = 0.0;
+    }
+
+    /// Generations due since the previous tick.
+    pub fn tick(&mut self, now: Instant) -> u32 {
+        let elapsed = now.saturating_duration_since(self.last_tick).as_secs_f64();
+        self.last_tick = now;
+        if self.paused {
+            return 0;
+        }
+
+        match self.rate {
+            Rate::Unlimited => self.batch,
+            Rate::PerSecond(rate) => {
+                self.pending += elapsed * rate.max(0.0);
+                let due = self.pending.floor();
+                self.pending -= due;
+                if due > self.max_catch_up as f64 {
+                    self.pending = 0.0;
+                    self.max_catch_up
+                } else {
+                    due as u32
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{grid::Grid, pattern::macrocell::Macrocell, rule::Rule, topology::Topology};
+
+/// A backend able to advance a Game of Life grid.
+pub trait Simulator {
+    /// Advance one generation.
+    fn step(&mut self);
+
+    /// Advance `n` generations.
+    fn step_n(&mut self, n: u32) {
+        for _ in 0..n {
+            self.step();
+        }
+    }
+
+    /// Generations stepped so far.
+    fn generation(&self) -> u64;
+
+    /// Copy the current generation out.
+    fn read_cells(&mut self) -> Grid;
+
+    /// Replace the current generation.
+    fn write_cells(&mut self, grid: &Grid);
+
+    /// Change the birth/survival rule used by later steps.
+    fn set_rule(&mut self, rule: Rule);
+
+    /// Change how the grid edges are joined, a torus until this is called.
+    fn set_topology(&mut self, topology: Topology);
+}
+
+/// An engine for the unbounded plane, `SparseLife` or `HashLife`. Cells have `i64` coordinates
+/// and only Life-like rules without B0 can run, anything else would fill the plane.
+pub trait UnboundedSimulator {
+    /// An empty plane. Panics if `rule` cannot run on it.
+    fn new(rule: Rule) -> Self
+    where
+        Self: Sized;
+
+    /// The live cells of `grid` with its top left corner at `(x, y)`.
+    fn from_grid(grid: &Grid, rule: Rule, (x, y): (i64, i64)) -> Self
+    where
+        Self: Sized,
+    {
+        let mut life = Self::new(rule);
+        for gy in 0..grid.height {
+            for gx in 0..grid.width {
+                if grid.get(gx, gy) != 0 {
+                    life.set(x + gx as i64, y + gy as i64, true);
+                }
+            }
+        }
+        life
+    }
+
+    /// A macrocell tree with its root centred on the origin like Golly does, and its
+    /// generation. Any non-zero state is alive.
+    fn from_macrocell(macrocell: &Macrocell, rule: Rule) -> Self
+    where
+        Self: Sized;
+
+    /// The live cells as a macrocell tree, with the rule and generation.
+    fn to_macrocell(&self) -> Macrocell;
+
+    /// Advance `n` generations.
+    fn step_n(&mut self, n: u64);
+
+    fn rule(&self) -> Rule;
+
+    /// Generations stepped so far.
+    fn generation(&self) -> u64;
+
+    fn get(&self, x: i64, y: i64) -> bool;
+
+    fn set(&mut self, x: i64, y: i64, alive: bool);
+
+    fn population(&self) -> u64;
+
+    /// Smallest and largest live cell coordinates, both inclusive, `None` if nothing is alive.
+    fn bounding_box(&self) -> Option<((i64, i64), (i64, i64))>;
+
+    /// The bounding box as a grid, with the world position of its top left cell. The caller
+    /// checks the bounding box fits in a grid.
+    fn to_grid(&self) -> Option<(Grid, (i64, i64))>;
+}
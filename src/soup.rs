@@ -0,0 +1,85 @@
+use std::str::FromStr;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::grid::Grid;
+
+/// Symmetry of a square soup, as used by census searches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    /// No symmetry.
+    C1,
+    /// Unchanged by a half turn.
+    C2,
+    /// Unchanged by a quarter turn.
+    C4,
+    /// Unchanged by every rotation and reflection of the square.
+    D8,
+}
+
+impl FromStr for Symmetry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "C1" => Ok(Symmetry::C1),
+            "C2" => Ok(Symmetry::C2),
+            "C4" => Ok(Symmetry::C4),
+            "D8" => Ok(Symmetry::D8),
+            _ => Err(format!("unknown symmetry `{s}`, expected C1, C2, C4 or D8")),
+        }
+    }
+}
+
+impl Symmetry {
+    /// Every image of `(x, y)` in a `size` square under the symmetry group.
+    fn orbit(self, size: u32, x: u32, y: u32) -> Vec<(u32, u32)> {
+        let m = |i: u32| size - 1 - i;
+        match self {
+            Symmetry::C1 => vec![(x, y)],
+            Symmetry::C2 => vec![(x, y), (m(x), m(y))],
+            Symmetry::C4 => vec![(x, y), (m(y), x), (m(x), m(y)), (y, m(x))],
+            Symmetry::D8 => vec![
+                (x, y),
+                (m(y), x),
+                (m(x), m(y)),
+                (y, m(x)),
+                (m(x), y),
+                (x, m(y)),
+                (y, x),
+                (m(y), m(x)),
+            ],
+        }
+    }
+}
+
+/// A `size` square soup with the given symmetry, reproducible from `seed`.
+pub fn symmetric(size: u32, density: f64, symmetry: Symmetry, seed: u64) -> Grid {
+    let base = Grid::random(size, size, density, seed);
+    let mut grid = Grid::empty(size, size);
+    for y in 0..size {
+        for x in 0..size {
+            // Every cell of an orbit copies the same representative.
+            let (rx, ry) = symmetry.orbit(size, x, y).into_iter().min().unwrap();
+            grid.set(x, y, base.get(rx, ry));
+        }
+    }
+    grid
+}
+
+/// Overwrite a rectangle of `grid` with random cells, leaving the rest alone.
+pub fn fill_rect(
+    grid: &mut Grid,
+    (x, y): (u32, u32),
+    (width, height): (u32, u32),
+    density: f64,
+    seed: u64,
+) {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    for gy in y..(y + height).min(grid.height) {
+        for gx in x..(x + width).min(grid.width) {
+            grid.set(gx, gy, rng.gen_bool(density.clamp(0.0, 1.0)) as u32);
+        }
+    }
+}
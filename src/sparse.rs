@@ -0,0 +1,261 @@
+pub mod render;
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    grid::Grid, hashlife::HashLife, pattern::macrocell::Macrocell, rule::Rule,
+    simulator::UnboundedSimulator,
+};
+
+/// Cells along each side of a chunk, one `u64` per row.
+pub const CHUNK_SIZE: i64 = 64;
+
+/// A `CHUNK_SIZE` square of cells, bit `x` of `rows[y]` is the cell at `(x, y)`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub rows: [u64; CHUNK_SIZE as usize],
+}
+
+impl Chunk {
+    fn empty() -> Box<Self> {
+        Box::new(Self {
+            rows: [0; CHUNK_SIZE as usize],
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.iter().all(|&row| row == 0)
+    }
+
+    pub fn population(&self) -> u32 {
+        self.rows.iter().map(|row| row.count_ones()).sum()
+    }
+
+    /// Live cells relative to the chunk's top left corner.
+    pub fn live_cells(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.rows.iter().enumerate().flat_map(|(y, &row)| {
+            (0..CHUNK_SIZE)
+                .filter(move |x| row >> x & 1 == 1)
+                .map(move |x| (x, y as i64))
+        })
+    }
+}
+
+/// Life on an unbounded plane, only chunks with live cells are stored so patterns can grow
+/// without limit. Rules with B0 would fill the whole plane and are not supported.
+pub struct SparseLife {
+    pub rule: Rule,
+    pub generation: u64,
+    /// Keyed by chunk coordinates, the cell `(x, y)` is in chunk `(x / 64, y / 64)` rounded down.
+    chunks: HashMap<(i64, i64), Box<Chunk>>,
+}
+
+impl SparseLife {
+    fn split(x: i64, y: i64) -> ((i64, i64), usize, usize) {
+        let key = (x.div_euclid(CHUNK_SIZE), y.div_euclid(CHUNK_SIZE));
+        let (x, y) = (x.rem_euclid(CHUNK_SIZE), y.rem_euclid(CHUNK_SIZE));
+        (key, x as usize, y as usize)
+    }
+
+    /// Kill every cell and start counting generations over.
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.generation = 0;
+    }
+
+    /// Stored chunks with their chunk coordinates, none of them empty.
+    pub fn chunks(&self) -> impl Iterator<Item = ((i64, i64), &Chunk)> {
+        self.chunks
+            .iter()
+            .map(|(&key, chunk)| (key, chunk.as_ref()))
+    }
+
+    pub fn chunk(&self, key: (i64, i64)) -> Option<&Chunk> {
+        self.chunks.get(&key).map(Box::as_ref)
+    }
+
+    /// Every live cell, in no particular order.
+    pub fn live_cells(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.chunks().flat_map(|((cx, cy), chunk)| {
+            chunk
+                .live_cells()
+                .map(move |(x, y)| (cx * CHUNK_SIZE + x, cy * CHUNK_SIZE + y))
+        })
+    }
+
+    pub fn step(&mut self) {
+        // Births can only happen next to live cells, so only neighbours of stored chunks need a
+        // look.
+        let mut candidates = HashSet::with_capacity(self.chunks.len() * 4);
+        for &(cx, cy) in self.chunks.keys() {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    candidates.insert((cx + dx, cy + dy));
+                }
+            }
+        }
+
+        let mut next = HashMap::with_capacity(self.chunks.len());
+        for key in candidates {
+            let chunk = self.step_chunk(key);
+            if !chunk.is_empty() {
+                next.insert(key, chunk);
+            }
+        }
+        self.chunks = next;
+        self.generation += 1;
+    }
+
+    /// Next generation of one chunk, 64 cells of a row at a time with bit-sliced counters.
+    fn step_chunk(&self, (cx, cy): (i64, i64)) -> Box<Chunk> {
+        let last = CHUNK_SIZE as usize - 1;
+        let around: [[Option<&Chunk>; 3]; 3] = std::array::from_fn(|dy| {
+            std::array::from_fn(|dx| self.chunk((cx + dx as i64 - 1, cy + dy as i64 - 1)))
+        });
+        let row = |dx: i64, dy: i64, y: usize| {
+            around[(dy + 1) as usize][(dx + 1) as usize].map_or(0, |chunk| chunk.rows[y])
+        };
+        // The row at `y` in this chunk's column, with the cells just left and right of it
+        // shifted in from the neighbouring chunks.
+        let neighbourhood = |y: isize| {
+            let (dy, y) = if y < 0 {
+                (-1, last)
+            } else if y as usize > last {
+                (1, 0)
+            } else {
+                (0, y as usize)
+            };
+            let centre = row(0, dy, y);
+            let west = centre << 1 | row(-1, dy, y) >> last;
+            let east = centre >> 1 | row(1, dy, y) << last;
+            (west, centre, east)
+        };
+
+        let mut next = Chunk::empty();
+        for y in 0..CHUNK_SIZE as isize {
+            let (above_w, above, above_e) = neighbourhood(y - 1);
+            let (west, centre, east) = neighbourhood(y);
+            let (below_w, below, below_e) = neighbourhood(y + 1);
+            if above | centre | below | west | east | above_w | above_e | below_w | below_e == 0 {
+                continue;
+            }
+
+            // Four bit planes of the neighbour count, enough for 0..=8.
+            let mut count = [0u64; 4];
+            for word in [above_w, above, above_e, west, east, below_w, below, below_e] {
+                let mut carry = word;
+                for bit in count.iter_mut() {
+                    let overflow = *bit & carry;
+                    *bit ^= carry;
+                    carry = overflow;
+                }
+            }
+
+            let (mut born, mut survive) = (0, 0);
+            for n in 0..=8 {
+                let equal = count.iter().enumerate().fold(!0u64, |acc, (i, &bit)| {
+                    acc & if n >> i & 1 == 1 { bit } else { !bit }
+                });
+                if self.rule.birth >> n & 1 == 1 {
+                    born |= equal;
+                }
+                if self.rule.survival >> n & 1 == 1 {
+                    survive |= equal;
+                }
+            }
+            next.rows[y as usize] = centre & survive | !centre & born;
+        }
+        next
+    }
+}
+
+impl UnboundedSimulator for SparseLife {
+    fn new(rule: Rule) -> Self {
+        assert!(
+            rule.birth & 1 == 0,
+            "B0 rules cannot run on an infinite plane"
+        );
+        assert!(
+            rule.is_life_like(),
+            "{rule} cannot run on an infinite plane"
+        );
+        Self {
+            rule,
+            generation: 0,
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Only the subtrees with live cells are walked, there is no grid in between.
+    fn from_macrocell(macrocell: &Macrocell, rule: Rule) -> Self {
+        let mut life = Self::new(rule);
+        let half = 1i64 << (macrocell.root().level() - 1);
+        macrocell.for_each_cell(|x, y, _| life.set(x as i64 - half, y as i64 - half, true));
+        life.generation = macrocell.generation.unwrap_or(0);
+        life
+    }
+
+    fn get(&self, x: i64, y: i64) -> bool {
+        let (key, x, y) = Self::split(x, y);
+        self.chunks
+            .get(&key)
+            .is_some_and(|chunk| chunk.rows[y] >> x & 1 == 1)
+    }
+
+    fn set(&mut self, x: i64, y: i64, alive: bool) {
+        let (key, x, y) = Self::split(x, y);
+        if alive {
+            self.chunks.entry(key).or_insert_with(Chunk::empty).rows[y] |= 1 << x;
+        } else if let Some(chunk) = self.chunks.get_mut(&key) {
+            chunk.rows[y] &= !(1 << x);
+            if chunk.is_empty() {
+                self.chunks.remove(&key);
+            }
+        }
+    }
+
+    fn population(&self) -> u64 {
+        self.chunks.values().map(|c| c.population() as u64).sum()
+    }
+
+    fn bounding_box(&self) -> Option<((i64, i64), (i64, i64))> {
+        let mut cells = self.live_cells();
+        let first = cells.next()?;
+        Some(cells.fold((first, first), |(min, max), (x, y)| {
+            ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)))
+        }))
+    }
+
+    fn to_grid(&self) -> Option<(Grid, (i64, i64))> {
+        let ((min_x, min_y), (max_x, max_y)) = self.bounding_box()?;
+        let mut grid = Grid::empty((max_x - min_x + 1) as u32, (max_y - min_y + 1) as u32);
+        for (x, y) in self.live_cells() {
+            grid.set((x - min_x) as u32, (y - min_y) as u32, 1);
+        }
+        Some((grid, (min_x, min_y)))
+    }
+
+    /// Built through `HashLife`, so the plane never has to fit in a grid.
+    fn to_macrocell(&self) -> Macrocell {
+        let mut life = HashLife::new(self.rule);
+        for (x, y) in self.live_cells() {
+            life.set(x, y, true);
+        }
+        life.generation = self.generation;
+        life.to_macrocell()
+    }
+
+    fn step_n(&mut self, n: u64) {
+        for _ in 0..n {
+            self.step();
+        }
+    }
+
+    fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+}
@@ -0,0 +1,174 @@
+use wgpu::{
+    util::DeviceExt, BindGroupLayout, Buffer, BufferUsages, ColorWrites, Device, Queue, RenderPass,
+    RenderPipeline, TextureFormat, VertexAttribute,
+};
+
+use crate::{camera::Camera, vertex::Vertex};
+
+use super::{SparseLife, CHUNK_SIZE};
+
+/// One live cell, drawn as an instance of the cell quad by `chunk.wgsl`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CellInstance {
+    pub cell: [i32; 2],
+}
+
+impl CellInstance {
+    const ATTRS: [VertexAttribute; 1] = wgpu::vertex_attr_array![1 => Sint32x2];
+    pub fn vertex_buffer_layout<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as _,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRS,
+        }
+    }
+}
+
+/// Chunk coordinates of the first and last chunk, both inclusive, that a `width` x `height`
+/// window shows.
+pub fn visible_chunks(camera: &Camera, width: u32, height: u32) -> ((i64, i64), (i64, i64)) {
+    let top_left = camera.screen_to_world(0.0, 0.0, width, height);
+    let bottom_right = camera.screen_to_world(width as f64, height as f64, width, height);
+    // Cell `(x, y)` covers world x..x + 1 and -y - 1..-y.
+    let min = (top_left.x.floor() as i64, (-top_left.y).floor() as i64 - 1);
+    let max = (
+        bottom_right.x.floor() as i64,
+        (-bottom_right.y).ceil() as i64,
+    );
+    (
+        (min.0.div_euclid(CHUNK_SIZE), min.1.div_euclid(CHUNK_SIZE)),
+        (max.0.div_euclid(CHUNK_SIZE), max.1.div_euclid(CHUNK_SIZE)),
+    )
+}
+
+/// Draws a `SparseLife` by uploading the live cells of the visible chunks each frame.
+pub struct ChunkRenderer {
+    pub render_pipline: RenderPipeline,
+    pub vertex_arr: Vec<Vertex>,
+    pub vertex_buffer: Buffer,
+    pub instance_buffer: Buffer,
+    pub instance_count: u32,
+}
+
+impl ChunkRenderer {
+    pub fn new(
+        device: &Device,
+        proj_bind_group_layout: &BindGroupLayout,
+        format: TextureFormat,
+    ) -> Self {
+        let vertex_arr = Vertex::rect().to_vec();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Chunk Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertex_arr),
+            usage: BufferUsages::VERTEX,
+        });
+        let instance_buffer = Self::create_instance_buffer(device, 1024);
+
+        let render_pipline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Chunk Render Pipline Layout"),
+                bind_group_layouts: &[proj_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let chunk_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Chunk Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../../assets/shader/chunk.wgsl").into()),
+        });
+
+        let render_pipline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Chunk Render Pipline"),
+            layout: Some(&render_pipline_layout),
+            vertex: wgpu::VertexState {
+                module: &chunk_shader,
+                entry_point: "vs_main",
+                buffers: &[
+                    Vertex::vertex_buffer_layout(),
+                    CellInstance::vertex_buffer_layout(),
+                ],
+            },
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &chunk_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        Self {
+            render_pipline,
+            vertex_arr,
+            vertex_buffer,
+            instance_buffer,
+            instance_count: 0,
+        }
+    }
+
+    fn create_instance_buffer(device: &Device, capacity: u64) -> Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Cell Instance Buffer"),
+            size: capacity * std::mem::size_of::<CellInstance>() as u64,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Upload the live cells the camera can see, growing the instance buffer if needed.
+    pub fn prepare(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        life: &SparseLife,
+        camera: &Camera,
+        width: u32,
+        height: u32,
+    ) {
+        let (min, max) = visible_chunks(camera, width, height);
+        let visible =
+            |(cx, cy): (i64, i64)| (min.0..=max.0).contains(&cx) && (min.1..=max.1).contains(&cy);
+
+        let mut instances = vec![];
+        for (key, chunk) in life.chunks().filter(|&(key, _)| visible(key)) {
+            let origin = (key.0 * CHUNK_SIZE, key.1 * CHUNK_SIZE);
+            instances.extend(chunk.live_cells().map(|(x, y)| CellInstance {
+                cell: [(origin.0 + x) as i32, (origin.1 + y) as i32],
+            }));
+        }
+
+        let size = std::mem::size_of_val(instances.as_slice()) as u64;
+        if size > self.instance_buffer.size() {
+            let capacity = (instances.len() as u64).next_power_of_two();
+            self.instance_buffer = Self::create_instance_buffer(device, capacity);
+        }
+        queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+        self.instance_count = instances.len() as u32;
+    }
+
+    /// Draw the cells from the last `prepare`, the projection must be bound at group 0.
+    pub fn draw<'a>(&'a self, render_pass: &mut RenderPass<'a>) {
+        render_pass.set_pipeline(&self.render_pipline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        render_pass.draw(0..self.vertex_arr.len() as u32, 0..self.instance_count);
+    }
+}
@@ -0,0 +1,248 @@
+//! Hexagonal and triangular cells, stored on the square grid the way Golly does it.
+//!
+//! A hexagonal grid is the square one with every row shifted half a cell right of the row
+//! below it, which leaves a cell six neighbours: its Moore neighbours but the top right and
+//! bottom left ones. Triangles alternate pointing up and down along a row, the cell at
+//! `(x, y)` points up when `x + y` is even, and a cell shares its base with the one above or
+//! below it.
+
+use glam::Vec2;
+
+use crate::{
+    topology::{Edge, Topology},
+    vertex::Vertex,
+};
+
+const SQUARE: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+const HEXAGONAL: [(i32, i32); 6] = [(-1, -1), (0, -1), (-1, 0), (1, 0), (0, 1), (1, 1)];
+/// Neighbours of a triangle pointing up, the first 3 share an edge with it and the others a
+/// corner. Those of a triangle pointing down are mirrored top to bottom.
+const TRIANGULAR: [(i32, i32); 12] = [
+    (-1, 0),
+    (1, 0),
+    (0, 1),
+    (-2, 0),
+    (2, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-2, 1),
+    (-1, 1),
+    (1, 1),
+    (2, 1),
+];
+
+const SQRT_3: f32 = 1.732_050_8;
+/// Distance between the rows of hexagons or triangles, cells are 1 wide.
+pub const ROW_HEIGHT: f32 = SQRT_3 / 2.0;
+/// Distance from the centre of a hexagon to its corners.
+pub const HEX_RADIUS: f32 = 1.0 / SQRT_3;
+
+/// Shape of the cells and which of them are neighbours, chosen by a rulestring suffix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tiling {
+    Square,
+    /// Hexagons with 6 neighbours, `H`.
+    Hexagonal,
+    /// Triangles with the 12 neighbours they share a corner with, `L`.
+    Triangular,
+    /// Triangles with only the 3 neighbours they share an edge with, `LE`.
+    TriangularVonNeumann,
+}
+
+impl Tiling {
+    /// Split the tiling suffix off a rulestring, `B2/S34H` is `B2/S34` on hexagons.
+    pub fn strip_suffix(s: &str) -> (&str, Tiling) {
+        let s = s.trim();
+        for (suffix, tiling) in [
+            ("LE", Self::TriangularVonNeumann),
+            ("L", Self::Triangular),
+            ("H", Self::Hexagonal),
+        ] {
+            let at = s.len().saturating_sub(suffix.len());
+            if s.is_char_boundary(at) && s[at..].eq_ignore_ascii_case(suffix) {
+                return (&s[..at], tiling);
+            }
+        }
+        (s, Self::Square)
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Self::Square => "",
+            Self::Hexagonal => "H",
+            Self::Triangular => "L",
+            Self::TriangularVonNeumann => "LE",
+        }
+    }
+
+    /// How many neighbours every cell has.
+    pub fn neighbour_count(self) -> u32 {
+        match self {
+            Self::Square => 8,
+            Self::Hexagonal => 6,
+            Self::Triangular => 12,
+            Self::TriangularVonNeumann => 3,
+        }
+    }
+
+    /// Whether the triangle at `(x, y)` points up, with its base at the bottom.
+    pub fn points_up((x, y): (u32, u32)) -> bool {
+        (x + y) % 2 == 0
+    }
+
+    /// Offsets of the neighbours of the cell at `(x, y)`. Must match `cp_tiled` in
+    /// `compute.wgsl`.
+    pub fn neighbours(self, cell: (u32, u32)) -> impl Iterator<Item = (i32, i32)> {
+        let offsets: &[(i32, i32)] = match self {
+            Self::Square => &SQUARE,
+            Self::Hexagonal => &HEXAGONAL,
+            Self::Triangular => &TRIANGULAR,
+            Self::TriangularVonNeumann => &TRIANGULAR[..3],
+        };
+        let flip =
+            matches!(self, Self::Triangular | Self::TriangularVonNeumann) && !Self::points_up(cell);
+        offsets
+            .iter()
+            .map(move |&(dx, dy)| (dx, if flip { -dy } else { dy }))
+    }
+
+    /// An error if joining the edges of a `width` x `height` grid as `topology` does leaves a
+    /// seam of cells that don't fit together, such as two triangles pointing up side by side.
+    /// Triangles alternate along both axes, so wrapping needs an even size and a twist, which
+    /// also turns them upside down, depends on the size of both axes. Mirrored hexagons never
+    /// line up with the skewed rows.
+    pub fn check_topology(
+        self,
+        topology: Topology,
+        (width, height): (u32, u32),
+    ) -> Result<(), String> {
+        let edges = [topology.x, topology.y];
+        let error = match self {
+            Self::Square => None,
+            Self::Hexagonal if edges.contains(&Edge::Twist) => {
+                Some("hexagons cannot be joined with a twist".to_string())
+            }
+            Self::Hexagonal => None,
+            Self::Triangular | Self::TriangularVonNeumann => {
+                let odd_sum = (width + height) % 2 == 1;
+                match (topology.x, topology.y) {
+                    (Edge::Wrap, _) if width % 2 == 1 => Some("an even width to wrap"),
+                    (_, Edge::Wrap) if height % 2 == 1 => Some("an even height to wrap"),
+                    (Edge::Twist, _) if odd_sum => {
+                        Some("a width and height both even or both odd to twist left to right")
+                    }
+                    (_, Edge::Twist) if !odd_sum => {
+                        Some("one of the width and height odd to twist top to bottom")
+                    }
+                    _ => None,
+                }
+                .map(|need| format!("triangles need {need}"))
+            }
+        };
+        match error {
+            Some(error) => Err(format!("{error}, the grid is {width} x {height}")),
+            None => Ok(()),
+        }
+    }
+
+    /// The code `mesh.wgsl` and `compute.wgsl` know the tiling by.
+    pub fn uniform(self) -> u32 {
+        self as u32
+    }
+
+    /// One cell, a little smaller than the space it takes so the cells are apart, with its
+    /// bounding box starting at the origin. Triangles point up and are turned around in
+    /// `mesh.wgsl` for the cells pointing down.
+    pub fn vertices(self) -> Vec<Vertex> {
+        match self {
+            Self::Square => Vertex::rect().into(),
+            Self::Hexagonal => Vertex::hexagon().into(),
+            Self::Triangular | Self::TriangularVonNeumann => Vertex::triangle().into(),
+        }
+    }
+
+    /// Bottom left corner of the bounding box of the cell at `(x, y)` in world units, from
+    /// the bottom left of a grid `height` rows tall. Row 0 is at the top. Must match
+    /// `cell_corner` in `mesh.wgsl`.
+    pub fn corner(self, (x, y): (u32, u32), height: u32) -> Vec2 {
+        let (x, row) = (x as f32, (height - 1 - y) as f32);
+        match self {
+            Self::Square => Vec2::new(x, row),
+            Self::Hexagonal => Vec2::new(x + row / 2.0, row * ROW_HEIGHT),
+            Self::Triangular | Self::TriangularVonNeumann => Vec2::new(x / 2.0, row * ROW_HEIGHT),
+        }
+    }
+
+    /// World units the cells of a `width` x `height` grid cover.
+    pub fn size(self, (width, height): (u32, u32)) -> Vec2 {
+        let (width, height) = (width as f32, height as f32);
+        match self {
+            Self::Square => Vec2::new(width, height),
+            Self::Hexagonal => Vec2::new(
+                width + (height - 1.0) / 2.0,
+                (height - 1.0) * ROW_HEIGHT + 2.0 * HEX_RADIUS,
+            ),
+            Self::Triangular | Self::TriangularVonNeumann => {
+                Vec2::new((width + 1.0) / 2.0, height * ROW_HEIGHT)
+            }
+        }
+    }
+
+    /// The cell covering `pos`, in world units from the bottom left of a `width` x `height`
+    /// grid, `None` outside the grid.
+    pub fn cell_at(self, pos: Vec2, (width, height): (u32, u32)) -> Option<(u32, u32)> {
+        // Column and row counted from the bottom.
+        let (x, row) = match self {
+            Self::Square => (pos.x.floor() as i64, pos.y.floor() as i64),
+            Self::Hexagonal => {
+                // The nearest centre, the rows and columns rounded to are off by at most one.
+                let row = ((pos.y - HEX_RADIUS) / ROW_HEIGHT).round() as i64;
+                let x = (pos.x - 0.5 - row as f32 / 2.0).round() as i64;
+                let centre = |(x, row): (i64, i64)| {
+                    Vec2::new(
+                        x as f32 + row as f32 / 2.0 + 0.5,
+                        row as f32 * ROW_HEIGHT + HEX_RADIUS,
+                    )
+                };
+                (row - 1..=row + 1)
+                    .flat_map(|row| (x - 1..=x + 1).map(move |x| (x, row)))
+                    .min_by(|&a, &b| {
+                        let (a, b) = (centre(a).distance(pos), centre(b).distance(pos));
+                        a.total_cmp(&b)
+                    })
+                    .unwrap()
+            }
+            Self::Triangular | Self::TriangularVonNeumann => {
+                let row = (pos.y / ROW_HEIGHT).floor() as i64;
+                // How far up the row, 0 on the bases of the triangles pointing up.
+                let t = pos.y / ROW_HEIGHT - row as f32;
+                let y = height as i64 - 1 - row;
+                // The triangle at `x` spans `x / 2..x / 2 + 1`, two of them overlap `pos.x`.
+                let right = (2.0 * pos.x).floor() as i64;
+                let x = [right - 1, right]
+                    .into_iter()
+                    .find(|&x| {
+                        let half_width = match (x + y).rem_euclid(2) {
+                            0 => (1.0 - t) / 2.0,
+                            _ => t / 2.0,
+                        };
+                        (pos.x - (x as f32 / 2.0 + 0.5)).abs() <= half_width
+                    })
+                    .unwrap_or(right);
+                (x, row)
+            }
+        };
+        let inside = (0..width as i64).contains(&x) && (0..height as i64).contains(&row);
+        inside.then(|| (x as u32, height - 1 - row as u32))
+    }
+}
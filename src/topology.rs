@@ -0,0 +1,194 @@
+use std::{fmt, str::FromStr};
+
+/// What happens to a neighbour lookup that crosses a pair of opposite grid edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    /// Cells past the edge are always dead.
+    Dead,
+    /// The edges are joined, like a torus.
+    Wrap,
+    /// The edges are joined with a half twist, mirroring the other axis.
+    Twist,
+}
+
+/// How the edges of a finite grid are joined, `x` for the left/right pair, `y` for top/bottom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Topology {
+    pub x: Edge,
+    pub y: Edge,
+}
+
+impl Topology {
+    pub const PLANE: Topology = Topology {
+        x: Edge::Dead,
+        y: Edge::Dead,
+    };
+    pub const TORUS: Topology = Topology {
+        x: Edge::Wrap,
+        y: Edge::Wrap,
+    };
+    /// Joined left to right, top to bottom with a twist.
+    pub const KLEIN: Topology = Topology {
+        x: Edge::Wrap,
+        y: Edge::Twist,
+    };
+    pub const CROSS_SURFACE: Topology = Topology {
+        x: Edge::Twist,
+        y: Edge::Twist,
+    };
+    /// Joined left to right, dead at the top and bottom.
+    pub const CYLINDER: Topology = Topology {
+        x: Edge::Wrap,
+        y: Edge::Dead,
+    };
+
+    /// The `vec2<u32>` uniform read by `compute.wgsl`.
+    pub fn uniform(&self) -> [u32; 2] {
+        let code = |edge| match edge {
+            Edge::Dead => 0,
+            Edge::Wrap => 1,
+            Edge::Twist => 2,
+        };
+        [code(self.x), code(self.y)]
+    }
+
+    /// The cell `(dx, dy)` away from `(x, y)`, or `None` if it is past a dead edge.
+    ///
+    /// The left/right edges are resolved before the top/bottom ones, `compute.wgsl` does the
+    /// same so the two agree on the corners of twisted grids.
+    pub fn neighbour(
+        &self,
+        (x, y): (u32, u32),
+        (dx, dy): (i32, i32),
+        (width, height): (u32, u32),
+    ) -> Option<(u32, u32)> {
+        let (width, height) = (width as i64, height as i64);
+        let mut x = x as i64 + dx as i64;
+        let mut y = y as i64 + dy as i64;
+        if x < 0 || x >= width {
+            match self.x {
+                Edge::Dead => return None,
+                Edge::Wrap => x = x.rem_euclid(width),
+                Edge::Twist => {
+                    x = x.rem_euclid(width);
+                    y = height - 1 - y;
+                }
+            }
+        }
+        if y < 0 || y >= height {
+            match self.y {
+                Edge::Dead => return None,
+                Edge::Wrap => y = y.rem_euclid(height),
+                Edge::Twist => {
+                    y = y.rem_euclid(height);
+                    x = width - 1 - x;
+                }
+            }
+        }
+        Some((x as u32, y as u32))
+    }
+}
+
+impl Default for Topology {
+    fn default() -> Self {
+        Self::TORUS
+    }
+}
+
+/// A Golly bounded grid such as `T100,100`, `K100*,100` or `P100,100`.
+///
+/// A zero dimension is unbounded in Golly, here it means that axis keeps dead edges at whatever
+/// size the grid is given, so `T100,0` is a cylinder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundedGrid {
+    pub topology: Topology,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl BoundedGrid {
+    /// The bounded grid of a `width` x `height` grid joined by `topology`. Dead axes of a
+    /// joined grid are 0, the way Golly writes cylinders.
+    pub fn new(topology: Topology, (width, height): (u32, u32)) -> Self {
+        let size = |edge, size| match (edge, topology) {
+            (Edge::Dead, topology) if topology != Topology::PLANE => 0,
+            _ => size,
+        };
+        Self {
+            topology,
+            width: size(topology.x, width),
+            height: size(topology.y, height),
+        }
+    }
+}
+
+impl FromStr for BoundedGrid {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("expected a bounded grid like T100,100 or K100*,100, got `{s}`");
+        let mut chars = s.trim().chars();
+        let kind = chars.next().ok_or_else(error)?.to_ascii_uppercase();
+        let (width, height) = chars.as_str().split_once(',').ok_or_else(error)?;
+        if width.contains('+') || height.contains('+') {
+            return Err(format!("shifted edges are not supported, got `{s}`"));
+        }
+        let twisted_width = width.ends_with('*');
+        let twisted_height = height.ends_with('*');
+        let width: u32 = width.trim_end_matches('*').parse().map_err(|_| error())?;
+        let height: u32 = height.trim_end_matches('*').parse().map_err(|_| error())?;
+
+        let mut topology = match (kind, twisted_width, twisted_height) {
+            ('P', false, false) => Topology::PLANE,
+            ('T', false, false) => Topology::TORUS,
+            // A twist on the width means the top and bottom edges are the twisted pair.
+            ('K', false, true) => Topology {
+                x: Edge::Twist,
+                y: Edge::Wrap,
+            },
+            ('K', _, false) => Topology::KLEIN,
+            ('C', false, false) => Topology::CROSS_SURFACE,
+            ('S', _, _) => return Err("sphere grids are not supported".to_string()),
+            _ => return Err(error()),
+        };
+        if width == 0 || height == 0 {
+            if matches!(kind, 'K' | 'C') {
+                return Err(format!("`{s}` needs both dimensions"));
+            }
+            if width == 0 {
+                topology.x = Edge::Dead;
+            }
+            if height == 0 {
+                topology.y = Edge::Dead;
+            }
+        }
+        Ok(Self {
+            topology,
+            width,
+            height,
+        })
+    }
+}
+
+impl fmt::Display for BoundedGrid {
+    /// The Golly form `FromStr` reads back. Errors for grids Golly has no form for: a twisted
+    /// axis next to a dead one, a wrapped or twisted axis of size 0, or a dead axis of a torus
+    /// that is not 0, since that is how Golly writes a cylinder.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (width, height) = (self.width, self.height);
+        let (x, y) = (self.topology.x, self.topology.y);
+        let size_ok = |edge, size| (edge == Edge::Dead) == (size == 0);
+        if (x, y) != (Edge::Dead, Edge::Dead) && !(size_ok(x, width) && size_ok(y, height)) {
+            return Err(fmt::Error);
+        }
+        match (x, y) {
+            (Edge::Dead, Edge::Dead) => write!(f, "P{width},{height}"),
+            // Cylinders are tori with the dead axis 0.
+            (Edge::Wrap | Edge::Dead, Edge::Wrap | Edge::Dead) => write!(f, "T{width},{height}"),
+            (Edge::Wrap, Edge::Twist) => write!(f, "K{width}*,{height}"),
+            (Edge::Twist, Edge::Wrap) => write!(f, "K{width},{height}*"),
+            (Edge::Twist, Edge::Twist) => write!(f, "C{width},{height}"),
+            (Edge::Twist, Edge::Dead) | (Edge::Dead, Edge::Twist) => Err(fmt::Error),
+        }
+    }
+}
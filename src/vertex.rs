@@ -1,5 +1,7 @@
 use wgpu::VertexAttribute;
 
+use crate::tiling::{HEX_RADIUS, ROW_HEIGHT};
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
@@ -20,12 +22,32 @@ impl Vertex {
         }
     }
 
+    /// A cell of `Tiling::Triangular`, 1 wide and pointing up, shrunk like `rect` towards its
+    /// centre.
     pub fn triangle() -> [Vertex; 3] {
-        [
-            Vertex::new([0.1, 0.1]),
-            Vertex::new([0.9, 0.1]),
-            Vertex::new([0.1, 0.9]),
-        ]
+        let centre = [0.5, ROW_HEIGHT / 3.0];
+        let shrink = |x: f32, y: f32| {
+            Vertex::new([
+                centre[0] + (x - centre[0]) * 0.8,
+                centre[1] + (y - centre[1]) * 0.8,
+            ])
+        };
+        [shrink(0.0, 0.0), shrink(1.0, 0.0), shrink(0.5, ROW_HEIGHT)]
+    }
+
+    /// A cell of `Tiling::Hexagonal`, 1 wide with a corner at the top, as 4 triangles.
+    pub fn hexagon() -> [Vertex; 12] {
+        let corners: [Vertex; 6] = std::array::from_fn(|i| {
+            let angle = (90.0 + 60.0 * i as f32).to_radians();
+            Vertex::new([
+                0.5 + 0.8 * HEX_RADIUS * angle.cos(),
+                HEX_RADIUS + 0.8 * HEX_RADIUS * angle.sin(),
+            ])
+        });
+        std::array::from_fn(|i| match i % 3 {
+            0 => corners[0],
+            corner => corners[i / 3 + corner],
+        })
     }
 
     pub fn rect() -> [Vertex; 6] {
@@ -0,0 +1,206 @@
+use game_of_life::{
+    cpu::CpuSimulator,
+    gpu::{CellLayout, GpuSimulator},
+    grid::Grid,
+    packed::PackedSimulator,
+    rule::Rule,
+    simulator::Simulator,
+    topology::{BoundedGrid, Edge, Topology},
+};
+
+/// Random soup stepped on both backends must stay identical.
+fn compare(width: u32, height: u32, generations: u32) {
+    compare_topology(Topology::TORUS, width, height, generations);
+}
+
+fn compare_topology(topology: Topology, width: u32, height: u32, generations: u32) {
+    for layout in [CellLayout::Word, CellLayout::Bit] {
+        compare_layout(layout, topology, width, height, generations);
+    }
+}
+
+fn compare_layout(
+    layout: CellLayout,
+    topology: Topology,
+    width: u32,
+    height: u32,
+    generations: u32,
+) {
+    let grid = Grid::random(width, height, 0.3, 1);
+    let gpu = GpuSimulator::headless_with_layout(&grid, Rule::LIFE, layout);
+    let Some(mut gpu) = pollster::block_on(gpu) else {
+        eprintln!("no adapter, skipping gpu check");
+        return;
+    };
+    let mut cpu = CpuSimulator::new(grid, Rule::LIFE);
+    cpu.set_topology(topology);
+    gpu.set_topology(topology);
+
+    for _ in 0..generations {
+        cpu.step();
+        gpu.step();
+    }
+    assert_eq!(cpu.generation(), generations as u64);
+    assert_eq!(gpu.generation(), generations as u64);
+    let (cpu, gpu) = (cpu.read_cells(), gpu.read_cells());
+    assert_eq!((gpu.width, gpu.height), (width, height));
+    assert!(cpu.cell_arr == gpu.cell_arr, "{layout:?} {topology:?}");
+}
+
+#[test]
+fn non_square() {
+    compare(77, 13, 20);
+}
+
+#[test]
+fn multi_million_cells() {
+    compare(2048, 1024, 2);
+}
+
+#[test]
+fn every_topology() {
+    for text in [
+        "P31,17", "T31,17", "K31*,17", "K31,17*", "C31,17", "T31,0", "T0,17",
+    ] {
+        let bounds: BoundedGrid = text.parse().unwrap();
+        compare_topology(bounds.topology, 31, 17, 30);
+    }
+}
+
+#[test]
+fn rows_across_word_boundaries() {
+    for (width, height) in [(32, 9), (33, 9), (64, 5), (95, 12)] {
+        for topology in [
+            Topology::PLANE,
+            Topology::TORUS,
+            Topology::KLEIN,
+            Topology::CROSS_SURFACE,
+            Topology::CYLINDER,
+            Topology {
+                x: Edge::Twist,
+                y: Edge::Wrap,
+            },
+            Topology {
+                x: Edge::Dead,
+                y: Edge::Twist,
+            },
+        ] {
+            compare_topology(topology, width, height, 25);
+        }
+    }
+}
+
+#[test]
+fn packed_cell_access() {
+    let grid = Grid::empty(40, 3);
+    let Some(mut gpu) = pollster::block_on(GpuSimulator::headless(&grid, Rule::LIFE)) else {
+        eprintln!("no adapter, skipping gpu check");
+        return;
+    };
+    assert_eq!(gpu.layout, CellLayout::Bit);
+    for x in [0, 31, 32, 39] {
+        gpu.set_cell(x, 1, 1);
+    }
+    gpu.set_cell(31, 1, 0);
+    let cells = gpu.read_cells();
+    for x in 0..40 {
+        let expected = [0, 32, 39].contains(&x) as u32;
+        assert_eq!(pollster::block_on(gpu.get_cell(x, 1)), expected, "{x}");
+        assert_eq!(cells.get(x, 1), expected, "{x}");
+    }
+}
+
+#[test]
+fn set_cells_in_batches() {
+    let grid = Grid::random(70, 9, 0.4, 5);
+    for layout in [CellLayout::Word, CellLayout::Bit] {
+        let gpu = GpuSimulator::headless_with_layout(&grid, Rule::LIFE, layout);
+        let Some(mut gpu) = pollster::block_on(gpu) else {
+            eprintln!("no adapter, skipping gpu check");
+            return;
+        };
+        let mut cpu = CpuSimulator::new(grid.clone(), Rule::LIFE);
+        // Edits land in whichever buffer holds the current generation.
+        gpu.step();
+        cpu.step();
+
+        // More edits than one dispatch takes, the same words over and over.
+        let mut edits = vec![];
+        for i in 0..700u32 {
+            edits.push((i * 7 % 70, i % 9, i % 3 % 2));
+        }
+        gpu.set_cells(&edits);
+        let mut expected = cpu.read_cells();
+        for &(x, y, v) in &edits {
+            expected.set(x, y, v);
+        }
+        cpu.write_cells(&expected);
+        assert!(gpu.read_cells().cell_arr == expected.cell_arr, "{layout:?}");
+
+        gpu.step();
+        cpu.step();
+        assert!(
+            gpu.read_cells().cell_arr == cpu.read_cells().cell_arr,
+            "{layout:?}"
+        );
+    }
+}
+
+#[test]
+fn write_cells_resizes() {
+    // A blinker on a larger grid than the simulators started with.
+    let mut grid = Grid::empty(40, 7);
+    for x in 3..6 {
+        grid.set(x, 3, 1);
+    }
+    let mut end = Grid::empty(40, 7);
+    for y in 2..5 {
+        end.set(4, y, 1);
+    }
+    let check = |simulator: &mut dyn Simulator| {
+        simulator.write_cells(&grid);
+        simulator.step();
+        let cells = simulator.read_cells();
+        assert_eq!((cells.width, cells.height), (40, 7));
+        assert!(cells.cell_arr == end.cell_arr);
+    };
+
+    let start = Grid::empty(4, 4);
+    check(&mut CpuSimulator::new(start.clone(), Rule::LIFE));
+    check(&mut PackedSimulator::new(start.clone(), Rule::LIFE));
+    for layout in [CellLayout::Word, CellLayout::Bit] {
+        let gpu = GpuSimulator::headless_with_layout(&start, Rule::LIFE, layout);
+        let Some(mut gpu) = pollster::block_on(gpu) else {
+            eprintln!("no adapter, skipping gpu check");
+            return;
+        };
+        check(&mut gpu);
+    }
+}
+
+#[test]
+fn set_rule_moves_the_cells_to_another_layout() {
+    let grid = Grid::random(45, 20, 0.4, 3);
+    let Some(mut gpu) = pollster::block_on(GpuSimulator::headless(&grid, Rule::LIFE)) else {
+        eprintln!("no adapter, skipping gpu check");
+        return;
+    };
+    let mut cpu = CpuSimulator::new(grid, Rule::LIFE);
+    assert_eq!(gpu.layout, CellLayout::Bit);
+    gpu.step_n(3);
+    cpu.step_n(3);
+
+    // Generations needs a word per cell.
+    let rule: Rule = "B2/S/C3".parse().unwrap();
+    gpu.set_rule(rule);
+    cpu.set_rule(rule);
+    assert_eq!(gpu.layout, CellLayout::Word);
+    assert!(gpu.read_cells().cell_arr == cpu.read_cells().cell_arr);
+    gpu.step_n(5);
+    cpu.step_n(5);
+    assert!(gpu.read_cells().cell_arr == cpu.read_cells().cell_arr);
+    assert_eq!(gpu.generation(), 8);
+
+    gpu.set_rule(Rule::LIFE);
+    assert_eq!(gpu.layout, CellLayout::Bit);
+}
@@ -0,0 +1,48 @@
+use game_of_life::camera::Camera;
+use glam::Vec2;
+
+#[test]
+fn fit_centres_grid() {
+    let mut camera = Camera::new();
+    camera.fit(Vec2::new(4096.0, 2048.0), 800, 600);
+    assert_eq!(camera.zoom, 800.0 / 4096.0);
+    assert_eq!(
+        camera.screen_to_world(0.0, 300.0, 800, 600),
+        Vec2::new(-2048.0, 0.0)
+    );
+}
+
+#[test]
+fn zoom_keeps_point_under_cursor() {
+    let mut camera = Camera::new();
+    let before = camera.screen_to_world(100.0, 50.0, 800, 600);
+    camera.zoom_at(8.0, 100.0, 50.0, 800, 600);
+    assert_eq!(camera.zoom, 8.0);
+    assert!(
+        camera
+            .screen_to_world(100.0, 50.0, 800, 600)
+            .distance(before)
+            < 1e-3
+    );
+
+    // Clamped, but the cursor point still stays put.
+    camera.zoom_at(1e6, 100.0, 50.0, 800, 600);
+    assert_eq!(camera.zoom, Camera::MAX_ZOOM);
+    assert!(
+        camera
+            .screen_to_world(100.0, 50.0, 800, 600)
+            .distance(before)
+            < 1e-3
+    );
+}
+
+#[test]
+fn pan_follows_cursor() {
+    let mut camera = Camera {
+        translation: Vec2::ZERO,
+        zoom: 4.0,
+    };
+    let grabbed = camera.screen_to_world(10.0, 10.0, 800, 600);
+    camera.pan(40.0, -20.0);
+    assert_eq!(camera.screen_to_world(50.0, -10.0, 800, 600), grabbed);
+}
@@ -0,0 +1,89 @@
+use std::{path::PathBuf, process::Command};
+
+use game_of_life::{
+    pattern::{rle, Pattern},
+    rule::Rule,
+};
+
+/// A fresh directory for one test's files.
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("game-of-life-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn headless(args: &[&str]) {
+    let status = Command::new(env!("CARGO_BIN_EXE_game-of-life"))
+        .args(["--headless", "--cpu"])
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success(), "{args:?}");
+}
+
+fn read(path: &PathBuf) -> Pattern {
+    rle::parse(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn bounded_grids_round_trip() {
+    let dir = scratch("bounded");
+    for (rule, bounds) in [
+        ("B3/S23:T50,50", "T50,50"),
+        ("B3/S23:K40*,30", "K40*,30"),
+        ("B3/S23:P20,30", "P20,30"),
+        ("B3/S23:T30,0", "T30,0"),
+    ] {
+        let (first, second) = (dir.join("first.rle"), dir.join("second.rle"));
+        headless(&[
+            "--rule",
+            rule,
+            "--seed",
+            "1",
+            "--generations",
+            "3",
+            "--output",
+            first.to_str().unwrap(),
+        ]);
+        let written = read(&first);
+        assert_eq!(written.rule, Some(Rule::LIFE), "{rule}");
+        assert_eq!(written.bounds, Some(bounds.parse().unwrap()), "{rule}");
+
+        // Reading it back keeps the grid and its edges.
+        headless(&[
+            first.to_str().unwrap(),
+            "--output",
+            second.to_str().unwrap(),
+        ]);
+        let read_back = read(&second);
+        assert_eq!(read_back.bounds, written.bounds, "{rule}");
+        assert!(read_back.grid.cell_arr == written.grid.cell_arr, "{rule}");
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn two_state_formats_refuse_other_states() {
+    let dir = scratch("states");
+    let output = dir.join("out.cells");
+    let run = Command::new(env!("CARGO_BIN_EXE_game-of-life"))
+        .args(["--headless", "--cpu", "--rule", "B2/S/C3", "--seed", "1"])
+        .args(["--generations", "2", "--output", output.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(!run.status.success());
+    assert!(String::from_utf8_lossy(&run.stderr).contains(".rle"));
+    assert!(!output.exists());
+
+    headless(&[
+        "--rule",
+        "B2/S/C3",
+        "--seed",
+        "1",
+        "--generations",
+        "2",
+        "--output",
+        dir.join("out.rle").to_str().unwrap(),
+    ]);
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,15 @@
+use game_of_life::edit::line;
+
+#[test]
+fn line_has_no_gaps() {
+    assert_eq!(line((2, 2), (2, 2)), vec![(2, 2)]);
+    assert_eq!(line((0, 0), (3, 0)), vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+    assert_eq!(line((3, 3), (0, 0)), vec![(3, 3), (2, 2), (1, 1), (0, 0)]);
+
+    let cells = line((0, 0), (7, 3));
+    assert_eq!((cells[0], cells[cells.len() - 1]), ((0, 0), (7, 3)));
+    for pair in cells.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        assert!(a.0.abs_diff(b.0) <= 1 && a.1.abs_diff(b.1) <= 1);
+    }
+}
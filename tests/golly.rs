@@ -0,0 +1,240 @@
+use game_of_life::{
+    cpu::CpuSimulator,
+    gpu::{CellLayout, GpuSimulator},
+    grid::Grid,
+    pattern::{Format, Pattern},
+    rule::{golly, tree::RuleTree, Rule, RuleError},
+    simulator::Simulator,
+    topology::Topology,
+};
+
+/// Every cell takes the state of the one north of it, so patterns move down a row per step.
+const FALL_TREE: &str = "\
+@RULE Fall
+@TREE
+num_states=2
+num_neighbors=4
+num_nodes=9
+1 0 0
+1 1 1
+2 0 0
+2 1 1
+3 2 2
+3 3 3
+4 4 4
+4 5 5
+5 6 7
+";
+
+/// The same rule as `FALL_TREE` written as a table on the Moore neighbourhood.
+const FALL_TABLE: &str = "\
+@RULE FallTable
+@TABLE
+n_states:2
+neighborhood:Moore
+symmetries:none
+var a={0,1}
+var b={0,1}
+var c={0,1}
+var d={0,1}
+var e={0,1}
+var f={0,1}
+var g={0,1}
+var h={0,1}
+a,0,b,c,d,e,f,g,h,0
+a,1,b,c,d,e,f,g,h,1
+";
+
+fn rows(rows: &[&str]) -> Grid {
+    let mut grid = Grid::empty(rows[0].len() as u32, rows.len() as u32);
+    for (y, row) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            grid.set(x as u32, y as u32, c.to_digit(10).unwrap());
+        }
+    }
+    grid
+}
+
+/// `start` stepped `generations` times must equal `end` on the CPU and the GPU.
+fn check(rule: &Rule, topology: Topology, start: &Grid, generations: u32, end: &Grid) {
+    let mut cpu = CpuSimulator::new(start.clone(), *rule);
+    cpu.set_topology(topology);
+    cpu.step_n(generations);
+    assert!(cpu.read_cells().cell_arr == end.cell_arr, "cpu {rule}");
+
+    let Some(mut gpu) = pollster::block_on(GpuSimulator::headless(start, *rule)) else {
+        eprintln!("no adapter, skipping gpu check");
+        return;
+    };
+    gpu.set_topology(topology);
+    gpu.step_n(generations);
+    assert!(gpu.read_cells().cell_arr == end.cell_arr, "gpu {rule}");
+}
+
+#[test]
+fn wireworld_by_name() {
+    let rule = Rule::resolve("WireWorld").unwrap();
+    assert_eq!(rule.to_string(), "WireWorld");
+    // Parsing never reads files.
+    assert_eq!("WireWorld".parse::<Rule>(), Err(RuleError::Format));
+    assert_eq!(rule.states, 4);
+    assert!(!rule.is_life_like());
+    let tree = rule.tree.as_ref().unwrap();
+    assert_eq!(tree.colors[1], [0, 128, 255]);
+    assert_eq!(tree.colors[3], [255, 128, 0]);
+
+    let text = "x = 3, y = 1, rule = WireWorld\nABC!\n";
+    let pattern = Pattern::parse(Format::Rle, text).unwrap();
+    assert_eq!(pattern.rule, Some(rule));
+    assert!(pattern.write(Format::Rle).contains("rule = WireWorld"));
+
+    assert!(matches!(
+        Rule::resolve("rules/Missing.rule"),
+        Err(RuleError::File(_))
+    ));
+}
+
+#[test]
+fn wireworld_electrons() {
+    let rule = Rule::resolve("rules/WireWorld.rule").unwrap();
+    // An electron runs along the wire, head first.
+    let start = rows(&["00000000", "21333333", "00000000"]);
+    let end = rows(&["00000000", "33321333", "00000000"]);
+    check(&rule, Topology::PLANE, &start, 3, &end);
+
+    // Two heads next to a wire cell make it a head, three do not.
+    let start = rows(&["01100", "00300", "00000", "01110", "00300"]);
+    let end = rows(&["02200", "00100", "00000", "02220", "00300"]);
+    check(&rule, Topology::PLANE, &start, 1, &end);
+}
+
+#[test]
+fn states_past_the_tree_are_empty() {
+    let rule = Rule::resolve("WireWorld").unwrap();
+    // Cells in states WireWorld doesn't have neither make heads nor read past the tree.
+    let start = rows(&["000", "939", "777"]);
+    let end = rows(&["000", "030", "000"]);
+    check(&rule, Topology::PLANE, &start, 1, &end);
+}
+
+#[test]
+fn tree_and_table_agree() {
+    let tree = Rule::from_tree(golly::parse(FALL_TREE).unwrap());
+    let table = Rule::from_tree(golly::parse(FALL_TABLE).unwrap());
+    assert_eq!(table.tree.as_ref().unwrap().neighbours, 8);
+
+    let start = rows(&["0100", "0110", "0000", "1000", "0000"]);
+    let end = rows(&["0000", "0100", "0110", "0000", "1000"]);
+    for rule in [&tree, &table] {
+        check(rule, Topology::TORUS, &start, 1, &end);
+        check(rule, Topology::TORUS, &start, 5, &start);
+    }
+}
+
+#[test]
+fn bound_variables_and_symmetries() {
+    let table = |symmetries: &str, transitions: &str| {
+        let rule = format!(
+            "@RULE Test\n@TABLE\nn_states:3\nneighborhood:vonNeumann\nsymmetries:{symmetries}\n\
+             var a={{1,2}}\n{transitions}"
+        );
+        Rule::from_tree(golly::parse(&rule).unwrap())
+    };
+
+    // `a` is bound, north and south must match and the new state is theirs.
+    let rule = table("none", "0,a,0,a,0,a\n");
+    let start = rows(&[
+        "010", "000", "010", "020", "000", "020", "010", "000", "020",
+    ]);
+    let end = rows(&[
+        "010", "010", "010", "020", "020", "020", "010", "000", "020",
+    ]);
+    check(&rule, Topology::PLANE, &start, 1, &end);
+
+    // A single neighbour with value 1 on any side gives birth to a 2 under rotate4, only to
+    // the north without symmetries.
+    let start = rows(&["00000", "00000", "00100", "00000", "00000"]);
+    let rotated = rows(&["00000", "00200", "02120", "00200", "00000"]);
+    let north = rows(&["00000", "00000", "00100", "00200", "00000"]);
+    check(
+        &table("rotate4", "0,1,0,0,0,2\n"),
+        Topology::PLANE,
+        &start,
+        1,
+        &rotated,
+    );
+    check(
+        &table("none", "0,1,0,0,0,2\n"),
+        Topology::PLANE,
+        &start,
+        1,
+        &north,
+    );
+    // Without commas when every state is one digit.
+    check(
+        &table("permute", "010002\n"),
+        Topology::PLANE,
+        &start,
+        1,
+        &rotated,
+    );
+}
+
+#[test]
+fn errors() {
+    let header = "@RULE Bad\n@TABLE\nn_states:2\nneighborhood:Moore\n";
+    for (body, expected) in [
+        ("neighborhood:hexagonal\n", "unsupported neighborhood"),
+        ("symmetries:rotate3\n", "unknown symmetries"),
+        ("var a={0,1}\n0,0,0,0,0,0,0,0,0,a\n", "not bound"),
+        ("0,0,0,0,0,0,0,0,0\n", "expected 10 states"),
+        ("0,0,0,0,0,0,0,0,0,2\n", "bad output"),
+        ("0,0,0,0,0,0,0,0,x,1\n", "neither a state nor a variable"),
+    ] {
+        let error = golly::parse(&format!("{header}{body}")).unwrap_err();
+        assert!(error.contains(expected), "{error}");
+    }
+
+    let error = golly::parse("@TABLE\nn_states:2\n").unwrap_err();
+    assert!(error.contains("missing @RULE"), "{error}");
+    let error = golly::parse("@RULE Empty\n@COLORS\n1 255 0 0\n").unwrap_err();
+    assert!(error.contains("no @TABLE or @TREE"), "{error}");
+
+    let bad_child = FALL_TREE.replace("5 6 7", "5 6 3");
+    let error = golly::parse(&bad_child).unwrap_err();
+    assert!(error.contains("bad child 3"), "{error}");
+    let missing = FALL_TREE.replace("num_nodes=9", "num_nodes=10");
+    assert!(golly::parse(&missing).is_err());
+}
+
+#[test]
+fn colors() {
+    let rule = format!("{FALL_TREE}@COLORS\n1 255 0 0\n");
+    let tree = golly::parse(&rule).unwrap();
+    assert_eq!(tree.colors, vec![[48, 48, 48], [255, 0, 0]]);
+
+    let tree = golly::parse(&FALL_TABLE.replace("n_states:2", "n_states:3")).unwrap();
+    assert_eq!(tree.colors, RuleTree::default_colors(3));
+    assert_eq!(tree.colors[1], [255, 0, 0]);
+    assert_eq!(tree.colors[2], [255, 255, 0]);
+
+    let gradient = "@COLORS\n0 0 255 255 255 0 # blue to yellow\n";
+    let tree = golly::parse(&format!("{FALL_TABLE}{gradient}")).unwrap();
+    assert_eq!(tree.colors[1], [0, 0, 255]);
+}
+
+#[test]
+fn switching_rules_on_the_gpu() {
+    let wireworld = Rule::resolve("WireWorld").unwrap();
+    let start = rows(&["00000000", "21333333", "00000000"]);
+    let end = rows(&["00000000", "33321333", "00000000"]);
+    let gpu = GpuSimulator::headless_with_layout(&start, Rule::LIFE, CellLayout::Word);
+    let Some(mut gpu) = pollster::block_on(gpu) else {
+        eprintln!("no adapter, skipping gpu check");
+        return;
+    };
+    gpu.set_topology(Topology::PLANE);
+    gpu.set_rule(wireworld);
+    gpu.step_n(3);
+    assert!(gpu.read_cells().cell_arr == end.cell_arr);
+}
@@ -0,0 +1,243 @@
+use game_of_life::{
+    grid::Grid,
+    hashlife::HashLife,
+    pattern::{
+        macrocell::{Macrocell, Node},
+        rle,
+    },
+    rule::Rule,
+    simulator::UnboundedSimulator,
+    sparse::SparseLife,
+};
+
+const GLIDER: &str = "x = 3, y = 3\nbo$2bo$3o!";
+
+const GOSPER_GUN: &str = "x = 36, y = 9
+24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b
+obo$10bo5bo7bo$11bo3bo$12b2o!";
+
+fn grid(rle: &str) -> Grid {
+    rle::parse(rle).unwrap().grid
+}
+
+fn sorted(mut cells: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    cells.sort();
+    cells
+}
+
+#[test]
+fn matches_sparse_life() {
+    let soup = Grid::random(50, 40, 0.4, 9);
+    for (rule, generations) in [
+        ("B3/S23", 1),
+        ("B3/S23", 7),
+        ("B3/S23", 100),
+        ("B36/S23", 37),
+    ] {
+        let rule: Rule = rule.parse().unwrap();
+        let mut hash = HashLife::from_grid(&soup, rule, (-25, -3));
+        let mut sparse = SparseLife::from_grid(&soup, rule, (-25, -3));
+        hash.step_n(generations);
+        sparse.step_n(generations);
+        assert_eq!(hash.generation, generations);
+        assert_eq!(
+            hash.population(),
+            sparse.population(),
+            "{rule} {generations}"
+        );
+        assert_eq!(
+            sorted(hash.live_cells()),
+            sorted(sparse.live_cells().collect())
+        );
+        assert_eq!(hash.bounding_box(), sparse.bounding_box());
+    }
+}
+
+#[test]
+fn step_size() {
+    let mut a = HashLife::from_grid(&grid(GOSPER_GUN), Rule::LIFE, (0, 0));
+    let mut b = HashLife::from_grid(&grid(GOSPER_GUN), Rule::LIFE, (0, 0));
+    a.set_step_log2(3);
+    assert_eq!(a.step_size(), 8);
+    a.step();
+    a.step();
+    b.step_n(16);
+    assert_eq!(a.generation, 16);
+    assert_eq!(sorted(a.live_cells()), sorted(b.live_cells()));
+    // `step_n` leaves the step size alone.
+    b.step_n(5);
+    assert_eq!(b.step_size(), 1);
+}
+
+#[test]
+fn glider_travels_far() {
+    let mut life = HashLife::from_grid(&grid(GLIDER), Rule::LIFE, (0, 0));
+    let start = life.to_grid().unwrap().0;
+    life.step_n(1 << 40);
+    assert_eq!(life.population(), 5);
+    let (cells, origin) = life.to_grid().unwrap();
+    assert_eq!(origin, (1 << 38, 1 << 38));
+    assert_eq!(cells.cell_arr, start.cell_arr);
+}
+
+#[test]
+fn steps_past_the_largest_power() {
+    // Made of 2^61 steps since 2^62 is too large for one.
+    let mut life = HashLife::from_grid(&grid(GLIDER), Rule::LIFE, (0, 0));
+    let start = life.to_grid().unwrap().0;
+    life.step_n(1 << 62);
+    assert_eq!(life.generation, 1 << 62);
+    let (cells, origin) = life.to_grid().unwrap();
+    assert_eq!(origin, (1 << 60, 1 << 60));
+    assert_eq!(cells.cell_arr, start.cell_arr);
+
+    // The generation stops at u64::MAX.
+    let mut life = HashLife::from_grid(&grid("x = 2, y = 2\n2o$2o!"), Rule::LIFE, (0, 0));
+    life.step_n(u64::MAX);
+    life.step_n(u64::MAX);
+    assert_eq!(life.generation, u64::MAX);
+    assert_eq!(life.population(), 4);
+}
+
+#[test]
+fn gun_for_a_billion_generations() {
+    let mut life = HashLife::from_grid(&grid(GOSPER_GUN), Rule::LIFE, (0, 0));
+    life.step_n(1 << 30);
+    assert_eq!(life.generation, 1 << 30);
+    // One five cell glider every 30 generations, plus the gun itself.
+    let gliders = (1u64 << 30) / 30;
+    let population = life.population();
+    assert!(
+        (gliders * 5..(gliders + 2) * 5 + 50).contains(&population),
+        "{population}"
+    );
+}
+
+#[test]
+fn garbage_collection_keeps_the_pattern() {
+    let mut life = HashLife::from_grid(&grid(GOSPER_GUN), Rule::LIFE, (0, 0));
+    let mut sparse = SparseLife::from_grid(&grid(GOSPER_GUN), Rule::LIFE, (0, 0));
+    life.step_n(300);
+    sparse.step_n(300);
+    let before = life.node_count();
+    life.collect_garbage();
+    assert!(life.node_count() < before);
+    assert_eq!(
+        sorted(life.live_cells()),
+        sorted(sparse.live_cells().collect())
+    );
+
+    // Collecting on every step must not change the outcome either.
+    life.gc_threshold = 0;
+    life.step_n(200);
+    sparse.step_n(200);
+    assert_eq!(
+        sorted(life.live_cells()),
+        sorted(sparse.live_cells().collect())
+    );
+}
+
+#[test]
+fn macrocell_round_trip() {
+    let mut life = HashLife::from_grid(&grid(GOSPER_GUN), Rule::LIFE, (-18, -4));
+    life.step_n(1000);
+    let written = life.to_macrocell().write();
+    assert!(written.contains("#G 1000"), "{written}");
+
+    let read = HashLife::from_macrocell(&Macrocell::parse(&written).unwrap(), Rule::LIFE);
+    assert_eq!(read.generation, 1000);
+    assert_eq!(sorted(read.live_cells()), sorted(life.live_cells()));
+
+    // The cropped pattern agrees with the tree.
+    let pattern = Macrocell::parse(&written).unwrap().to_pattern().unwrap();
+    assert_eq!(pattern.grid.cell_arr, life.to_grid().unwrap().0.cell_arr);
+}
+
+#[test]
+fn set_and_get() {
+    let mut life = HashLife::new(Rule::LIFE);
+    for (x, y) in [(0, 0), (-1, -1), (1000, -5000)] {
+        assert!(!life.get(x, y));
+        life.set(x, y, true);
+        assert!(life.get(x, y));
+    }
+    life.set(0, 0, false);
+    assert_eq!(life.population(), 2);
+    assert_eq!(life.bounding_box(), Some(((-1, -5000), (1000, -1))));
+}
+
+#[test]
+fn nothing_lost_at_the_root_edge() {
+    // Gliders about to cross the root's centre, whatever size the root ends up.
+    for x in [13, 29, 61, 125] {
+        let mut life = HashLife::from_grid(&grid(GLIDER), Rule::LIFE, (x, 0));
+        for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            life.set(dx - x, dy, true);
+        }
+        for step_log2 in 0..5 {
+            life.set_step_log2(step_log2);
+            life.step();
+            assert_eq!(life.population(), 9, "{x} {step_log2}");
+        }
+    }
+}
+
+#[test]
+fn macrocell_larger_than_a_grid() {
+    // Gliders in opposite corners of a square 2^40 cells wide.
+    let mut nodes = vec![Node::Leaf([0b010, 0b100, 0b111, 0, 0, 0, 0, 0])];
+    for level in 4..40 {
+        nodes.push(Node::Inner {
+            level,
+            children: [nodes.len(), 0, 0, 0],
+        });
+    }
+    let corner = nodes.len();
+    nodes.push(Node::Inner {
+        level: 40,
+        children: [corner, 0, 0, corner],
+    });
+    let macrocell = Macrocell {
+        nodes,
+        rule: None,
+        bounds: None,
+        generation: Some(3),
+        comments: vec![],
+    };
+    assert!(macrocell.to_pattern().is_err());
+
+    let hashlife = HashLife::from_macrocell(&macrocell, Rule::LIFE);
+    let sparse = SparseLife::from_macrocell(&macrocell, Rule::LIFE);
+    assert_eq!(sparse.population(), 10);
+    assert_eq!(sparse.generation, 3);
+    assert_eq!(
+        sorted(sparse.live_cells().collect()),
+        sorted(hashlife.live_cells())
+    );
+}
+
+/// The gun after `generations` on any engine.
+fn gun<L: UnboundedSimulator>(generations: u64) -> L {
+    let mut life = L::from_grid(&grid(GOSPER_GUN), Rule::LIFE, (-18, -4));
+    life.step_n(generations);
+    assert_eq!(life.generation(), generations);
+    life
+}
+
+#[test]
+fn engines_agree_through_the_trait() {
+    let sparse: SparseLife = gun(300);
+    let hash: HashLife = gun(300);
+    assert_eq!(sparse.population(), hash.population());
+    assert_eq!(sparse.bounding_box(), hash.bounding_box());
+    assert_eq!(
+        sparse.to_grid().unwrap().0.cell_arr,
+        hash.to_grid().unwrap().0.cell_arr
+    );
+
+    // Both write the same tree.
+    let written = sparse.to_macrocell().write();
+    assert!(written.contains("#G 300"), "{written}");
+    let read = HashLife::from_macrocell(&Macrocell::parse(&written).unwrap(), Rule::LIFE);
+    assert_eq!(sorted(read.live_cells()), sorted(hash.live_cells()));
+}
@@ -0,0 +1,136 @@
+use game_of_life::{
+    core::Core, grid::Grid, rule::Rule, simulator::UnboundedSimulator, sparse::SparseLife,
+};
+
+#[test]
+fn render_and_step_without_window() {
+    // A lone cell dies.
+    let mut grid = Grid::empty(2, 2);
+    grid.set(0, 0, 1);
+    let Some(mut core) = pollster::block_on(Core::headless(64, 64, grid, Rule::default())) else {
+        eprintln!("no adapter, skipping headless check");
+        return;
+    };
+
+    core.render();
+    let frame = core.read_frame().unwrap();
+    assert_eq!(frame.dimensions(), (64, 64));
+    // Cell (0, 0) is drawn top left, cell (0, 1) below it is dead.
+    let live = frame.get_pixel(16, 16);
+    let dead = frame.get_pixel(16, 48);
+    assert_ne!(live, dead);
+
+    core.step(1);
+    let cells = pollster::block_on(core.read_cells());
+    assert_eq!((cells.width, cells.height), (2, 2));
+    assert!(cells.cell_arr.iter().all(|&v| v == 0));
+
+    core.render();
+    let frame = core.read_frame().unwrap();
+    assert_eq!(frame.get_pixel(16, 16), dead);
+}
+
+#[test]
+fn cursor_picks_cells() {
+    let Some(core) = pollster::block_on(Core::headless(64, 32, Grid::empty(4, 2), Rule::default()))
+    else {
+        eprintln!("no adapter, skipping headless check");
+        return;
+    };
+
+    // Cells are 16 pixels, row 0 at the top.
+    assert_eq!(core.cursor_to_cell(1.0, 1.0), Some((0, 0)));
+    assert_eq!(core.cursor_to_cell(40.0, 24.0), Some((2, 1)));
+    assert_eq!(core.cursor_to_cell(63.0, 31.5), Some((3, 1)));
+    assert_eq!(core.cursor_to_cell(64.5, 8.0), None);
+
+    core.simulator.set_cell(2, 1, 1);
+    assert_eq!(pollster::block_on(core.simulator.get_cell(2, 1)), 1);
+    let cells = pollster::block_on(core.read_cells());
+    assert_eq!(cells.cell_arr.iter().sum::<u32>(), 1);
+    assert_eq!(cells.get(2, 1), 1);
+}
+
+#[test]
+fn render_sparse_plane() {
+    let Some(mut core) =
+        pollster::block_on(Core::headless(64, 64, Grid::empty(2, 2), Rule::default()))
+    else {
+        eprintln!("no adapter, skipping headless check");
+        return;
+    };
+    let mut life = SparseLife::new(Rule::LIFE);
+    // A glider far outside the grid, the view is fitted to it.
+    for (x, y) in [
+        (1001, 1000),
+        (1002, 1001),
+        (1000, 1002),
+        (1001, 1002),
+        (1002, 1002),
+    ] {
+        life.set(x, y, true);
+    }
+    core.use_sparse(life);
+    core.render();
+    let frame = core.read_frame().unwrap();
+    // Cell (1000, 1002) is live, cell (1000, 1000) is not.
+    assert_ne!(frame.get_pixel(10, 53), frame.get_pixel(10, 10));
+    assert_eq!(core.cursor_cell(10.0, 53.0), Some((1000, 1002)));
+
+    core.step(4);
+    assert_eq!(core.sparse.as_ref().unwrap().generation, 4);
+    assert!(core.sparse.as_ref().unwrap().get(1001, 1003));
+}
+
+#[test]
+fn render_dying_states() {
+    let mut grid = Grid::empty(4, 4);
+    grid.set(1, 1, 1);
+    grid.set(2, 1, 1);
+    let Some(mut core) = pollster::block_on(Core::headless(64, 64, grid, Rule::default())) else {
+        eprintln!("no adapter, skipping headless check");
+        return;
+    };
+
+    // Brian's Brain needs more than a bit per cell, the cells move over.
+    core.set_rule("B2/S/C3".parse().unwrap());
+    let cells = pollster::block_on(core.read_cells());
+    assert_eq!((cells.get(1, 1), cells.get(2, 1)), (1, 1));
+
+    core.step(1);
+    let cells = pollster::block_on(core.read_cells());
+    assert_eq!((cells.get(1, 1), cells.get(2, 1)), (2, 2));
+    assert_eq!((cells.get(1, 0), cells.get(1, 2)), (1, 1));
+
+    core.render();
+    let frame = core.read_frame().unwrap();
+    // Cells are 16 pixels, row 0 at the top.
+    let (dying, live, dead) = (
+        frame.get_pixel(24, 24),
+        frame.get_pixel(24, 40),
+        frame.get_pixel(8, 56),
+    );
+    assert_ne!(dying, live);
+    assert_ne!(dying, dead);
+    assert_ne!(live, dead);
+}
+
+#[test]
+fn start_with_dying_states() {
+    let mut grid = Grid::empty(4, 4);
+    grid.set(1, 1, 2);
+    grid.set(2, 1, 1);
+    let rule = "B2/S/C3".parse().unwrap();
+    let Some(mut core) = pollster::block_on(Core::headless(64, 64, grid, rule)) else {
+        eprintln!("no adapter, skipping headless check");
+        return;
+    };
+
+    // The dying cell survives being loaded into the window.
+    let cells = pollster::block_on(core.read_cells());
+    assert_eq!((cells.get(1, 1), cells.get(2, 1)), (2, 1));
+
+    core.step(1);
+    let cells = pollster::block_on(core.read_cells());
+    assert_eq!((cells.get(1, 1), cells.get(2, 1)), (0, 2));
+}
@@ -0,0 +1,163 @@
+use game_of_life::{
+    cpu::CpuSimulator,
+    gpu::{CellLayout, GpuSimulator},
+    grid::Grid,
+    rule::{Rule, RuleError},
+    simulator::Simulator,
+    topology::Topology,
+};
+
+const TLIFE: &str = "B3/S2-i34q";
+
+/// Blocks of `rule`'s table that give birth, the middle cell dead.
+fn births(rule: &str) -> Vec<usize> {
+    let rule: Rule = rule.parse().unwrap();
+    let table = rule.table();
+    (0..512)
+        .filter(|&block| block & 1 << 4 == 0 && table[block] == 1)
+        .collect()
+}
+
+#[test]
+fn parse_and_display() {
+    for rule in ["B2-a/S12", TLIFE, "B2ce3-cey/S1e2-ak5y/C4", "B04w8/S0"] {
+        let parsed: Rule = rule.parse().unwrap();
+        assert!(parsed.isotropic.is_some(), "{rule}");
+        assert!(!parsed.is_life_like());
+        assert_eq!(parsed.to_string(), rule);
+    }
+
+    // The shorter of the letters that apply and those that do not.
+    let rule: Rule = "B2ceakn/S3-".parse().unwrap();
+    assert_eq!(rule.to_string(), "B2-i/S3");
+
+    let life: Rule = "B3ceaiknjqry/S2-3".parse().unwrap();
+    assert_eq!(life, Rule::LIFE);
+
+    assert_eq!("B2z/S23".parse::<Rule>(), Err(RuleError::Letter('z', 2)));
+    assert_eq!("B3/S1a".parse::<Rule>(), Err(RuleError::Letter('a', 1)));
+    assert_eq!("B3/S8c".parse::<Rule>(), Err(RuleError::Letter('c', 8)));
+    assert_eq!("B3/S2-X".parse::<Rule>(), Err(RuleError::Digit('X')));
+}
+
+#[test]
+fn letters_cover_each_count_once() {
+    let letters = ["", "ce", "ceaikn", "ceaiknjqry", "ceaiknjqrytwz"];
+    for n in 0..=8usize {
+        let mut seen = Vec::new();
+        let names = letters[n.min(8 - n)];
+        let rules: Vec<String> = match names {
+            "" => vec![format!("B{n}/S")],
+            names => names.chars().map(|c| format!("B{n}{c}/S")).collect(),
+        };
+        for rule in rules {
+            let blocks = births(&rule);
+            assert!(!blocks.is_empty(), "{rule}");
+            for block in blocks {
+                assert_eq!(block.count_ones() as usize, n, "{rule}");
+                assert!(!seen.contains(&block), "{rule} overlaps another letter");
+                seen.push(block);
+            }
+        }
+        let configurations = (0..512usize)
+            .filter(|b| b & 1 << 4 == 0 && b.count_ones() as usize == n)
+            .count();
+        assert_eq!(seen.len(), configurations, "{n} neighbours");
+    }
+}
+
+#[test]
+fn letters_match_hensel_pictures() {
+    // Bit i is the cell i of the 3x3 block in reading order.
+    let block = |cells: &[usize]| cells.iter().map(|i| 1 << i).sum::<usize>();
+    for (rule, cells) in [
+        ("B2n/S", &[0, 8][..]),
+        ("B2i/S", &[1, 7]),
+        ("B2k/S", &[0, 5]),
+        ("B3y/S", &[0, 2, 7]),
+        ("B3i/S", &[0, 1, 2]),
+        ("B4c/S", &[0, 2, 6, 8]),
+        ("B4z/S", &[1, 2, 6, 7]),
+        ("B6i/S", &[0, 2, 3, 5, 6, 8]),
+        ("B7c/S", &[0, 1, 2, 3, 5, 6, 7]),
+    ] {
+        assert!(births(rule).contains(&block(cells)), "{rule}");
+    }
+    assert_eq!(births("B2n/S").len(), 2);
+    assert_eq!(births("B2a/S").len(), 8);
+    assert_eq!(births("B4e/S").len(), 1);
+}
+
+#[test]
+fn diagonal_pair_births() {
+    let rule: Rule = "B2n/S".parse().unwrap();
+    let mut start = Grid::empty(12, 12);
+    start.set(4, 4, 1);
+    start.set(6, 6, 1);
+    let mut end = Grid::empty(12, 12);
+    end.set(5, 5, 1);
+
+    let mut cpu = CpuSimulator::new(start.clone(), rule);
+    cpu.step();
+    assert!(cpu.read_cells().cell_arr == end.cell_arr);
+
+    // Two orthogonal neighbours are `2c`, not `2n`.
+    let mut cpu = CpuSimulator::new(Grid::empty(12, 12), rule);
+    let mut pair = Grid::empty(12, 12);
+    pair.set(4, 4, 1);
+    pair.set(6, 4, 1);
+    cpu.write_cells(&pair);
+    cpu.step();
+    assert!(cpu.read_cells().cell_arr == Grid::empty(12, 12).cell_arr);
+
+    let Some(mut gpu) = pollster::block_on(GpuSimulator::headless(&start, rule)) else {
+        eprintln!("no adapter, skipping gpu check");
+        return;
+    };
+    gpu.step();
+    assert!(gpu.read_cells().cell_arr == end.cell_arr);
+}
+
+#[test]
+fn gpu_matches_cpu() {
+    for rule in [TLIFE, "B2-a/S12", "B2e3-ck4y/S1c23-a5/C5", "B1e2i/S02k"] {
+        let rule: Rule = rule.parse().unwrap();
+        for topology in [Topology::TORUS, Topology::PLANE, Topology::KLEIN] {
+            let grid = Grid::random(37, 23, 0.4, 8);
+            let Some(mut gpu) = pollster::block_on(GpuSimulator::headless(&grid, rule)) else {
+                eprintln!("no adapter, skipping gpu check");
+                return;
+            };
+            let mut cpu = CpuSimulator::new(grid, rule);
+            cpu.set_topology(topology);
+            gpu.set_topology(topology);
+            cpu.step_n(12);
+            gpu.step_n(12);
+            assert!(
+                cpu.read_cells().cell_arr == gpu.read_cells().cell_arr,
+                "{rule} {topology:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn set_rule_switches_to_the_table() {
+    let grid = Grid::random(30, 30, 0.4, 2);
+    let tlife: Rule = TLIFE.parse().unwrap();
+    let mut cpu = CpuSimulator::new(grid.clone(), Rule::LIFE);
+    cpu.set_rule(tlife);
+    cpu.step_n(10);
+    let mut expected = CpuSimulator::new(grid.clone(), tlife);
+    expected.step_n(10);
+    assert!(cpu.read_cells().cell_arr == expected.read_cells().cell_arr);
+
+    let gpu = GpuSimulator::headless_with_layout(&grid, Rule::LIFE, CellLayout::Word);
+    let Some(mut gpu) = pollster::block_on(gpu) else {
+        eprintln!("no adapter, skipping gpu check");
+        return;
+    };
+    gpu.set_rule(tlife);
+    gpu.step_n(10);
+    assert!(gpu.read_cells().cell_arr == expected.read_cells().cell_arr);
+}
@@ -0,0 +1,135 @@
+use game_of_life::{
+    cpu::CpuSimulator,
+    gpu::GpuSimulator,
+    grid::Grid,
+    pattern::{Format, Pattern},
+    rule::{
+        larger::{LargerThanLife, Neighbourhood},
+        Rule, RuleError,
+    },
+    simulator::Simulator,
+    topology::Topology,
+};
+
+const BUGS: &str = "R5,C0,M1,S34..58,B34..45,NM";
+const MAJORITY: &str = "R4,C0,M1,S41..81,B41..81,NM";
+
+#[test]
+fn parse_and_display() {
+    let bugs: Rule = BUGS.parse().unwrap();
+    assert_eq!(bugs.states, 2);
+    assert_eq!(
+        bugs.larger,
+        Some(LargerThanLife {
+            range: 5,
+            neighbourhood: Neighbourhood::Moore,
+            middle: true,
+            survival: (34, 58),
+            birth: (34, 45),
+        })
+    );
+    assert_eq!(bugs.to_string(), BUGS);
+    assert!(!bugs.is_life_like());
+
+    let diamond: Rule = "r2,c3,m0,s1..2,b2,nn".parse().unwrap();
+    assert_eq!(diamond.to_string(), "R2,C3,M0,S1..2,B2..2,NN");
+    // Golly's default neighbourhood is Moore.
+    let moore: Rule = "R3,C0,M1,S5..9,B4..6".parse().unwrap();
+    assert_eq!(moore.to_string(), "R3,C0,M1,S5..9,B4..6,NM");
+
+    for bad in [
+        "R11,C0,M1,S34..58,B34..45,NM",
+        "R5,C0,M2,S34..58,B34..45,NM",
+        "R5,C0,M1,S34..58,B34..45,NX",
+        "R5,C0,M1,S34..122,B34..45,NM",
+        "R5,C0,M1,S58..34,B34..45,NM",
+        "R5,C0,M1,B34..45,NM",
+        "R5,C0,M1,S34..58,B34..45,NM,X",
+    ] {
+        assert!(
+            matches!(bad.parse::<Rule>(), Err(RuleError::Larger(_))),
+            "{bad}"
+        );
+    }
+}
+
+#[test]
+fn neighbourhood_sizes() {
+    assert_eq!(Neighbourhood::Moore.size(5), 121);
+    assert_eq!(Neighbourhood::VonNeumann.size(1), 5);
+    assert_eq!(Neighbourhood::VonNeumann.size(2), 13);
+    assert_eq!(Neighbourhood::Circular.size(1), 9);
+    assert_eq!(Neighbourhood::Circular.size(2), 21);
+    assert_eq!(Neighbourhood::Circular.size(10), 349);
+}
+
+#[test]
+fn rle_header_with_commas() {
+    let text = format!("x = 2, y = 1, rule = {BUGS}\n2o!\n");
+    let pattern = Pattern::parse(Format::Rle, &text).unwrap();
+    assert_eq!(pattern.rule, Some(BUGS.parse().unwrap()));
+    assert!(pattern.write(Format::Rle).contains(BUGS));
+}
+
+#[test]
+fn range_one_is_life() {
+    let grid = Grid::random(40, 30, 0.35, 4);
+    let larger: Rule = "R1,C0,M0,S2..3,B3..3,NM".parse().unwrap();
+    let mut life = CpuSimulator::new(grid.clone(), Rule::LIFE);
+    let mut cpu = CpuSimulator::new(grid.clone(), larger);
+    life.step_n(20);
+    cpu.step_n(20);
+    let expected = life.read_cells();
+    assert!(cpu.read_cells().cell_arr == expected.cell_arr);
+
+    let Some(mut gpu) = pollster::block_on(GpuSimulator::headless(&grid, larger)) else {
+        eprintln!("no adapter, skipping gpu check");
+        return;
+    };
+    gpu.step_n(20);
+    assert!(gpu.read_cells().cell_arr == expected.cell_arr);
+}
+
+/// The shader's shared tiles must count exactly like the CPU's plain loops.
+fn compare(rule: &str, topology: Topology, (width, height): (u32, u32), generations: u32) {
+    let rule: Rule = rule.parse().unwrap();
+    let grid = Grid::random(width, height, 0.5, 6);
+    let Some(mut gpu) = pollster::block_on(GpuSimulator::headless(&grid, rule)) else {
+        eprintln!("no adapter, skipping gpu check");
+        return;
+    };
+    let mut cpu = CpuSimulator::new(grid, rule);
+    cpu.set_topology(topology);
+    gpu.set_topology(topology);
+    cpu.step_n(generations);
+    gpu.step_n(generations);
+    let cells = cpu.read_cells();
+    assert!(
+        cells.cell_arr == gpu.read_cells().cell_arr,
+        "{rule} {topology:?} {width}x{height}"
+    );
+}
+
+#[test]
+fn gpu_matches_cpu() {
+    for rule in [
+        BUGS,
+        MAJORITY,
+        "R10,C0,M0,S100..200,B120..150,NM",
+        "R7,C0,M1,S20..40,B20..30,NN",
+        "R6,C0,M0,S30..60,B35..50,NC",
+        "R3,C4,M1,S8..16,B10..13,NM",
+    ] {
+        for topology in [Topology::TORUS, Topology::PLANE, Topology::KLEIN] {
+            compare(rule, topology, (45, 29), 6);
+        }
+    }
+}
+
+#[test]
+fn grid_smaller_than_the_neighbourhood() {
+    for topology in [Topology::TORUS, Topology::CROSS_SURFACE, Topology::PLANE] {
+        compare(BUGS, topology, (7, 5), 4);
+        compare("R10,C0,M1,S1..100,B3..90,NC", topology, (3, 9), 4);
+    }
+}
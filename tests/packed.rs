@@ -0,0 +1,100 @@
+use game_of_life::{
+    cpu::CpuSimulator,
+    gpu::GpuSimulator,
+    grid::Grid,
+    packed::PackedSimulator,
+    rule::Rule,
+    simulator::Simulator,
+    topology::{Edge, Topology},
+};
+
+const TOPOLOGIES: [Topology; 6] = [
+    Topology::PLANE,
+    Topology::TORUS,
+    Topology::KLEIN,
+    Topology::CROSS_SURFACE,
+    Topology::CYLINDER,
+    Topology {
+        x: Edge::Twist,
+        y: Edge::Wrap,
+    },
+];
+
+fn compare(topology: Topology, rule: Rule, (width, height): (u32, u32), threads: usize) {
+    let grid = Grid::random(width, height, 0.3, 2);
+    let mut reference = CpuSimulator::new(grid.clone(), rule);
+    let mut packed = PackedSimulator::new(grid, rule);
+    packed.threads = threads;
+    reference.set_topology(topology);
+    packed.set_topology(topology);
+
+    reference.step_n(20);
+    packed.step_n(20);
+    assert_eq!(packed.generation(), 20);
+    let (reference, packed) = (reference.read_cells(), packed.read_cells());
+    assert!(
+        reference.cell_arr == packed.cell_arr,
+        "{topology:?} {width}x{height} on {threads} threads"
+    );
+}
+
+#[test]
+fn matches_the_reference() {
+    for topology in TOPOLOGIES {
+        for size in [(1, 1), (5, 3), (63, 17), (64, 64), (65, 40), (200, 7)] {
+            compare(topology, Rule::LIFE, size, 1);
+        }
+    }
+}
+
+#[test]
+fn bands_match_one_thread() {
+    for topology in TOPOLOGIES {
+        for threads in [2, 3, 8] {
+            compare(topology, Rule::LIFE, (130, 257), threads);
+        }
+    }
+}
+
+#[test]
+fn other_rules() {
+    for rule in [
+        "B36/S23",
+        "B2/S",
+        "B1357/S1357",
+        "B0/S8",
+        "B012345678/S012345678",
+    ] {
+        compare(Topology::TORUS, rule.parse().unwrap(), (70, 70), 2);
+        compare(Topology::PLANE, rule.parse().unwrap(), (70, 70), 2);
+    }
+}
+
+#[test]
+fn matches_the_compute_shader() {
+    let grid = Grid::random(300, 200, 0.3, 3);
+    let Some(mut gpu) = pollster::block_on(GpuSimulator::headless(&grid, Rule::LIFE)) else {
+        eprintln!("no adapter, skipping gpu check");
+        return;
+    };
+    let mut packed = PackedSimulator::new(grid, Rule::LIFE);
+    packed.threads = 4;
+    for topology in [Topology::TORUS, Topology::KLEIN] {
+        gpu.set_topology(topology);
+        packed.set_topology(topology);
+        gpu.step_n(50);
+        packed.step_n(50);
+        assert!(gpu.read_cells().cell_arr == packed.read_cells().cell_arr);
+    }
+}
+
+#[test]
+fn write_and_read_back() {
+    let mut grid = Grid::empty(100, 3);
+    for x in [0, 63, 64, 99] {
+        grid.set(x, 1, 1);
+    }
+    let mut packed = PackedSimulator::new(Grid::empty(1, 1), Rule::LIFE);
+    packed.write_cells(&grid);
+    assert!(packed.read_cells().cell_arr == grid.cell_arr);
+}
@@ -0,0 +1,403 @@
+use std::path::Path;
+
+use game_of_life::{
+    grid::Grid,
+    pattern::{macrocell::Macrocell, rle, Format, Pattern},
+    rule::Rule,
+};
+
+const GLIDER: &str = "#N Glider
+#O Richard K. Guy
+#C The smallest, most common, and first discovered spaceship.
+x = 3, y = 3, rule = B3/S23
+bob$2bo$3o!
+";
+
+fn cells(grid: &Grid) -> Vec<(u32, u32)> {
+    (0..grid.height)
+        .flat_map(|y| (0..grid.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| grid.get(x, y) != 0)
+        .collect()
+}
+
+#[test]
+fn parse_glider() {
+    let pattern = rle::parse(GLIDER).unwrap();
+    assert_eq!(pattern.name.as_deref(), Some("Glider"));
+    assert_eq!(pattern.comments.len(), 1);
+    assert_eq!(pattern.rule, Some(Rule::LIFE));
+    assert_eq!((pattern.grid.width, pattern.grid.height), (3, 3));
+    assert_eq!(
+        cells(&pattern.grid),
+        [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]
+    );
+
+    let mut grid = Grid::empty(10, 10);
+    pattern.place(&mut grid, 4, 5);
+    assert_eq!(cells(&grid), [(5, 5), (6, 6), (4, 7), (5, 7), (6, 7)]);
+}
+
+#[test]
+fn parse_multiline_body_and_blank_rows() {
+    let pattern = rle::parse("x = 4, y = 5\n2o\nb\no3$\no2bo!").unwrap();
+    assert_eq!(
+        cells(&pattern.grid),
+        [(0, 0), (1, 0), (3, 0), (0, 3), (3, 3)]
+    );
+}
+
+#[test]
+fn write_glider() {
+    let pattern = rle::parse(GLIDER).unwrap();
+    assert_eq!(
+        rle::write(&pattern),
+        "#N Glider
+#C The smallest, most common, and first discovered spaceship.
+x = 3, y = 3, rule = B3/S23
+bo$2bo$3o!
+"
+    );
+}
+
+#[test]
+fn round_trip() {
+    let mut grid = Grid::random(200, 90, 0.3, 1);
+    // Leading and trailing blank rows and columns.
+    for x in 0..200 {
+        for y in [0, 1, 2, 88, 89] {
+            grid.set(x, y, 0);
+        }
+    }
+    for y in 0..90 {
+        grid.set(0, y, 0);
+    }
+    grid.set(50, 3, 1);
+
+    let mut pattern = Pattern::new(grid.clone());
+    pattern.rule = Some("B36/S23".parse().unwrap());
+    let text = rle::write(&pattern);
+    assert!(text.lines().all(|line| line.len() <= 70));
+
+    let read = rle::parse(&text).unwrap();
+    assert_eq!(read.rule, pattern.rule);
+    assert_eq!((read.grid.width, read.grid.height), (200, 90));
+    assert!(read.grid.cell_arr == grid.cell_arr);
+
+    let (cropped, offset) = Pattern::bounding_box(&grid).unwrap();
+    assert_eq!((offset.1, cropped.grid.height), (3, 85));
+    let read = rle::parse(&rle::write(&cropped)).unwrap();
+    let mut placed = Grid::empty(200, 90);
+    read.place(&mut placed, offset.0, offset.1);
+    assert!(placed.cell_arr == grid.cell_arr);
+}
+
+#[test]
+fn multi_state_round_trip() {
+    let mut grid = Grid::empty(4, 1);
+    grid.set(0, 0, 2);
+    grid.set(1, 0, 25);
+    grid.set(3, 0, 255);
+    let text = rle::write(&Pattern::new(grid.clone()));
+    assert!(text.ends_with("BpAbyO!\n"));
+    assert!(rle::parse(&text).unwrap().grid.cell_arr == grid.cell_arr);
+}
+
+#[test]
+fn errors_have_positions() {
+    let error = rle::parse("#C hi\nx = 3, y = 1\n2o\nbz!").err().unwrap();
+    assert_eq!((error.line, error.column), (4, 2));
+
+    let error = rle::parse("x = 2, y = 1\n3o!").err().unwrap();
+    assert_eq!((error.line, error.column), (2, 2));
+
+    let error = rle::parse("x = 2, y = 1, rule = B9/S").err().unwrap();
+    assert_eq!((error.line, error.column), (1, 14));
+
+    assert!(rle::parse("x = 2\no!").is_err());
+    let error = rle::parse("#N huge\nx = 4000000000, y = 4000000000\n!")
+        .err()
+        .unwrap();
+    assert_eq!((error.line, error.column), (2, 1));
+    assert!(rle::parse("x = 100000, y = 100000\n!").is_err());
+
+    // Runs can't move past the header, however long.
+    let error = rle::parse("x = 3, y = 3\n4294967295b4294967295b!")
+        .err()
+        .unwrap();
+    assert_eq!((error.line, error.column), (2, 11));
+    let error = rle::parse("x = 3, y = 3\n4294967295$4294967295$!")
+        .err()
+        .unwrap();
+    assert_eq!((error.line, error.column), (2, 11));
+    assert!(rle::parse("x = 3, y = 2\n3b$3o$!").is_ok());
+    assert!(Pattern::bounding_box(&Grid::empty(4, 4)).is_none());
+}
+
+const GLIDER_CELLS: &str = "!Name: Glider
+!The smallest spaceship.
+.O
+..O
+OOO
+";
+
+const GLIDER_105: &str = "#Life 1.05
+#D Glider
+#R 23/36
+#P -1 -1
+.*
+#P -1 0
+..*
+***
+";
+
+const GLIDER_106: &str = "#Life 1.06
+0 -1
+1 0
+-1 1
+0 1
+1 1
+";
+
+#[test]
+fn parse_other_formats() {
+    let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+
+    let pattern = Pattern::parse(Format::Plaintext, GLIDER_CELLS).unwrap();
+    assert_eq!(pattern.name.as_deref(), Some("Glider"));
+    assert_eq!(pattern.comments, ["The smallest spaceship."]);
+    assert_eq!((pattern.grid.width, pattern.grid.height), (3, 3));
+    assert_eq!(cells(&pattern.grid), glider);
+
+    let pattern = Pattern::parse(Format::Life105, GLIDER_105).unwrap();
+    assert_eq!(pattern.rule, Some("B36/S23".parse().unwrap()));
+    assert_eq!(cells(&pattern.grid), glider);
+
+    let pattern = Pattern::parse(Format::Life106, GLIDER_106).unwrap();
+    assert_eq!(cells(&pattern.grid), glider);
+}
+
+#[test]
+fn life_headers_after_blank_lines() {
+    let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+    for text in [
+        format!("\u{feff}{GLIDER_106}"),
+        format!("\n  \n{GLIDER_106}"),
+    ] {
+        assert_eq!(Format::sniff(&text), Some(Format::Life106));
+        let pattern = Pattern::parse(Format::Life106, &text).unwrap();
+        assert_eq!(cells(&pattern.grid), glider);
+    }
+    let pattern = Pattern::parse(Format::Life105, &format!("\n{GLIDER_105}")).unwrap();
+    assert_eq!(cells(&pattern.grid), glider);
+
+    // The first line is the header, not a cell.
+    let error = Pattern::parse(Format::Life106, "0 0\n1 1").err().unwrap();
+    assert_eq!(error.to_string(), "1:1: expected `#Life 1.06`, found `0 0`");
+}
+
+#[test]
+fn life_rules_round_trip() {
+    let (mut pattern, _) = Pattern::bounding_box(&Grid::random(8, 8, 0.5, 2)).unwrap();
+    for rule in [
+        "B36/S23",
+        "B2/S/C3",
+        "B2-a/S12",
+        "R2,C0,M1,S2..3,B3..3,NM",
+        "B2/S34H",
+    ] {
+        pattern.rule = Some(rule.parse().unwrap());
+        for format in [Format::Life105, Format::Rle] {
+            let read = Pattern::parse(format, &pattern.write(format)).unwrap();
+            assert_eq!(read.rule, pattern.rule, "{rule} {format:?}");
+        }
+    }
+}
+
+#[test]
+fn life_patterns_too_large() {
+    let error = Pattern::parse(Format::Life106, "#Life 1.06\n0 0\n100000 100000")
+        .err()
+        .unwrap();
+    assert_eq!((error.line, error.column), (1, 1));
+    let text = format!("#Life 1.06\n{} 0\n{} 0", i64::MIN, i64::MAX);
+    assert!(Pattern::parse(Format::Life106, &text).is_err());
+
+    // One long row and many short ones.
+    let text = format!("{}\n{}", ".".repeat(10_000), "O\n".repeat(10_000));
+    let error = Pattern::parse(Format::Plaintext, &text).err().unwrap();
+    assert_eq!((error.line, error.column), (1, 1));
+}
+
+#[test]
+fn detect_format() {
+    let path = Path::new("glider.lif");
+    assert_eq!(Format::detect(path, GLIDER_105), Some(Format::Life105));
+    assert_eq!(Format::detect(path, GLIDER_106), Some(Format::Life106));
+    assert_eq!(Format::detect(path, GLIDER), Some(Format::Rle));
+    assert_eq!(Format::detect(path, GLIDER_CELLS), Some(Format::Plaintext));
+    assert_eq!(Format::detect(path, "..O\nOO.\n"), Some(Format::Plaintext));
+    assert_eq!(Format::detect(Path::new("a.RLE"), ""), Some(Format::Rle));
+    assert_eq!(
+        Format::detect(Path::new("a.cells"), ""),
+        Some(Format::Plaintext)
+    );
+    assert_eq!(Format::detect(path, "hello"), None);
+
+    let pattern = Pattern::parse_detect(Path::new("glider"), GLIDER_106).unwrap();
+    assert_eq!(pattern.grid.cell_arr.iter().sum::<u32>(), 5);
+}
+
+#[test]
+fn round_trip_every_format() {
+    let (mut pattern, _) = Pattern::bounding_box(&Grid::random(30, 20, 0.3, 1)).unwrap();
+    pattern.rule = Some("B36/S23".parse().unwrap());
+    for format in [
+        Format::Rle,
+        Format::Plaintext,
+        Format::Life105,
+        Format::Life106,
+        Format::Macrocell,
+    ] {
+        let text = pattern.write(format);
+        assert_eq!(Format::sniff(&text), Some(format));
+        let read = Pattern::parse(format, &text).unwrap();
+        assert!(read.grid.cell_arr == pattern.grid.cell_arr, "{format:?}");
+    }
+}
+
+#[test]
+fn two_state_formats_write_only_live_cells() {
+    let mut grid = Grid::empty(3, 1);
+    grid.set(0, 0, 1);
+    grid.set(1, 0, 2);
+    grid.set(2, 0, 1);
+    let pattern = Pattern::new(grid);
+    for format in [Format::Plaintext, Format::Life105, Format::Life106] {
+        assert!(format.two_state());
+        let read = Pattern::parse(format, &pattern.write(format)).unwrap();
+        assert_eq!(cells(&read.grid), [(0, 0), (2, 0)], "{format:?}");
+    }
+    assert!(!Format::Rle.two_state() && !Format::Macrocell.two_state());
+}
+
+#[test]
+fn other_format_errors_have_positions() {
+    let error = Pattern::parse(Format::Plaintext, "!x\n.O\n.Ox")
+        .err()
+        .unwrap();
+    assert_eq!((error.line, error.column), (3, 3));
+
+    let error = Pattern::parse(Format::Life105, "#Life 1.05\n#P 0 0\n.*\n*o")
+        .err()
+        .unwrap();
+    assert_eq!((error.line, error.column), (4, 2));
+
+    let error = Pattern::parse(Format::Life106, "#Life 1.06\n0 0\n1 x")
+        .err()
+        .unwrap();
+    assert_eq!((error.line, error.column), (3, 1));
+    assert_eq!(error.to_string(), "3:1: expected `x y`, found `1 x`");
+}
+
+const GLIDER_MC: &str = "[M2] (golly 2.0)
+#R B3/S23
+#G 4
+$$$$$$.*$..*$
+***$
+4 1 0 2 0
+";
+
+#[test]
+fn parse_macrocell() {
+    let macrocell = Macrocell::parse(GLIDER_MC).unwrap();
+    assert_eq!(macrocell.generation, Some(4));
+    assert_eq!(macrocell.root().level(), 4);
+    assert_eq!(
+        macrocell.cells(),
+        [(1, 6, 1), (2, 7, 1), (0, 8, 1), (1, 8, 1), (2, 8, 1)]
+    );
+
+    let pattern = Pattern::parse(Format::Macrocell, GLIDER_MC).unwrap();
+    assert_eq!(pattern.rule, Some(Rule::LIFE));
+    assert_eq!(
+        cells(&pattern.grid),
+        [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]
+    );
+}
+
+#[test]
+fn macrocell_shares_nodes() {
+    // 64 copies of the same block only need one leaf.
+    let mut grid = Grid::empty(64, 64);
+    for y in (0..64).step_by(8) {
+        for x in (0..64).step_by(8) {
+            grid.set(x + 1, y + 1, 1);
+            grid.set(x + 2, y + 1, 1);
+        }
+    }
+    let macrocell = Macrocell::from_grid(&grid);
+    assert_eq!(macrocell.nodes.len(), 4);
+
+    let read = Macrocell::parse(&macrocell.write()).unwrap();
+    assert!(read.nodes == macrocell.nodes);
+}
+
+#[test]
+fn macrocell_round_trip() {
+    let (pattern, _) = Pattern::bounding_box(&Grid::random(100, 37, 0.3, 1)).unwrap();
+    let text = pattern.write(Format::Macrocell);
+    assert_eq!(Format::sniff(&text), Some(Format::Macrocell));
+    let read = Pattern::parse(Format::Macrocell, &text).unwrap();
+    assert!(read.grid.cell_arr == pattern.grid.cell_arr);
+
+    let mut grid = Grid::empty(5, 3);
+    grid.set(0, 0, 2);
+    grid.set(4, 2, 1);
+    grid.set(2, 1, 7);
+    let text = Pattern::new(grid.clone()).write(Format::Macrocell);
+    assert!(text.lines().any(|line| line.starts_with("1 ")));
+    let read = Pattern::parse(Format::Macrocell, &text).unwrap();
+    assert!(read.grid.cell_arr == grid.cell_arr);
+}
+
+#[test]
+fn macrocell_errors() {
+    let error = Macrocell::parse("[M2]\n.*$\n4 2 0 0 0").err().unwrap();
+    assert_eq!(error.line, 3);
+    let error = Macrocell::parse("[M2]\n1 0 1 0 0\n4 1 0 0 0")
+        .err()
+        .unwrap();
+    assert_eq!(error.line, 3);
+    let error = Macrocell::parse("[M2]\n.*x$").err().unwrap();
+    assert_eq!((error.line, error.column), (2, 3));
+    assert!(Macrocell::parse("x = 1, y = 1\no!").is_err());
+}
+
+#[test]
+fn macrocell_sizes_from_the_tree() {
+    // A few lines for a square 2^50 cells wide and full.
+    let mut text = String::from("[M2]\n");
+    text += &"********$".repeat(8);
+    for level in 4..=50 {
+        let child = level - 3;
+        text += &format!("\n{level} {child} {child} {child} {child}");
+    }
+    let macrocell = Macrocell::parse(&text).unwrap();
+    let side = 1 << 50;
+    assert_eq!(
+        macrocell.bounding_box(),
+        Some(((0, 0), (side - 1, side - 1)))
+    );
+    assert_eq!(macrocell.population(), u64::MAX);
+    assert!(Pattern::parse(Format::Macrocell, &text).is_err());
+
+    // Empty 2x2 nodes take no room.
+    let mut text = String::from("[M2]\n1 0 0 0 0\n2 1 0 0 1");
+    for level in 3..=50 {
+        text += &format!("\n{level} {} 0 0 0", level - 1);
+    }
+    let macrocell = Macrocell::parse(&text).unwrap();
+    assert_eq!(macrocell.bounding_box(), None);
+    let pattern = Pattern::parse(Format::Macrocell, &text).unwrap();
+    assert_eq!((pattern.grid.width, pattern.grid.height), (0, 0));
+}
@@ -0,0 +1,234 @@
+use game_of_life::{
+    cpu::CpuSimulator,
+    gpu::GpuSimulator,
+    grid::Grid,
+    rule::{Rule, RuleError},
+    simulator::Simulator,
+};
+
+fn pattern(rows: &[&str], x: u32, y: u32) -> Grid {
+    let mut grid = Grid::empty(40, 40);
+    for (dy, row) in rows.iter().enumerate() {
+        for (dx, c) in row.chars().enumerate() {
+            if c == 'O' {
+                grid.set(
+                    (x + dx as u32) % grid.width,
+                    (y + dy as u32) % grid.height,
+                    1,
+                );
+            }
+        }
+    }
+    grid
+}
+
+fn gpu(grid: &Grid, rule: Rule) -> Option<GpuSimulator> {
+    let simulator = pollster::block_on(GpuSimulator::headless(grid, rule));
+    if simulator.is_none() {
+        eprintln!("no adapter, skipping gpu check");
+    }
+    simulator
+}
+
+/// `start` stepped `period` generations must equal `end`, on every backend.
+fn check(start: &Grid, period: u32, end: &Grid) {
+    check_rule(Rule::LIFE, start, period, end);
+}
+
+fn check_rule(rule: Rule, start: &Grid, period: u32, end: &Grid) {
+    let mut cpu = CpuSimulator::new(start.clone(), rule);
+    cpu.step_n(period);
+    assert_eq!(cpu.read_cells().cell_arr, end.cell_arr, "cpu");
+
+    if let Some(mut gpu) = gpu(start, rule) {
+        gpu.step_n(period);
+        assert_eq!(gpu.read_cells().cell_arr, end.cell_arr, "gpu");
+    }
+}
+
+/// Oscillator with exact `period`: back to start after it, never before.
+fn check_period(start: &Grid, period: u32) {
+    check(start, period, start);
+
+    let mut cpu = CpuSimulator::new(start.clone(), Rule::LIFE);
+    for _ in 1..period {
+        cpu.step();
+        assert_ne!(cpu.read_cells().cell_arr, start.cell_arr, "cpu");
+    }
+
+    if let Some(mut gpu) = gpu(start, Rule::LIFE) {
+        for _ in 1..period {
+            gpu.step();
+            assert_ne!(gpu.read_cells().cell_arr, start.cell_arr, "gpu");
+        }
+    }
+}
+
+#[test]
+fn lone_cell_dies() {
+    check(&pattern(&["O"], 5, 5), 1, &Grid::empty(40, 40));
+}
+
+#[test]
+fn pair_is_not_born() {
+    // Every dead cell here has at most two neighbours.
+    check(&pattern(&["OO"], 5, 5), 1, &Grid::empty(40, 40));
+}
+
+#[test]
+fn block() {
+    check_period(&pattern(&["OO", "OO"], 5, 5), 1);
+}
+
+#[test]
+fn beehive() {
+    check_period(&pattern(&[".OO.", "O..O", ".OO."], 5, 5), 1);
+}
+
+#[test]
+fn blinker() {
+    let horizontal = pattern(&["OOO"], 5, 5);
+    let vertical = pattern(&["O", "O", "O"], 6, 4);
+    check(&horizontal, 1, &vertical);
+    check_period(&horizontal, 2);
+}
+
+#[test]
+fn toad() {
+    check_period(&pattern(&[".OOO", "OOO."], 5, 5), 2);
+}
+
+#[test]
+fn glider() {
+    let rows = [".O.", "..O", "OOO"];
+    check(&pattern(&rows, 5, 5), 4, &pattern(&rows, 6, 6));
+}
+
+#[test]
+fn glider_wraps() {
+    let rows = [".O.", "..O", "OOO"];
+    check(&pattern(&rows, 38, 38), 4, &pattern(&rows, 39, 39));
+}
+
+#[test]
+fn pulsar() {
+    let rows = [
+        "..OOO...OOO..",
+        ".............",
+        "O....O.O....O",
+        "O....O.O....O",
+        "O....O.O....O",
+        "..OOO...OOO..",
+        ".............",
+        "..OOO...OOO..",
+        "O....O.O....O",
+        "O....O.O....O",
+        "O....O.O....O",
+        ".............",
+        "..OOO...OOO..",
+    ];
+    check_period(&pattern(&rows, 10, 10), 3);
+}
+
+#[test]
+fn parse_rulestring() {
+    let life = Rule::LIFE;
+    assert_eq!("B3/S23".parse(), Ok(life));
+    assert_eq!("b3/s23".parse(), Ok(life));
+    assert_eq!("S23/B3".parse(), Ok(life));
+    assert_eq!("23/3".parse(), Ok(life));
+
+    let seeds: Rule = "B2/S".parse().unwrap();
+    assert_eq!((seeds.birth, seeds.survival), (1 << 2, 0));
+    assert_eq!("/2".parse(), Ok(seeds));
+
+    let highlife: Rule = "B36/S23".parse().unwrap();
+    assert_eq!(highlife.to_string(), "B36/S23");
+
+    assert_eq!("B3S23".parse::<Rule>(), Err(RuleError::Format));
+    assert_eq!("B39/S23".parse::<Rule>(), Err(RuleError::Digit('9')));
+    assert_eq!("B3/B23".parse::<Rule>(), Err(RuleError::Duplicate('B')));
+}
+
+#[test]
+fn parse_generations() {
+    let brain: Rule = "B2/S/C3".parse().unwrap();
+    assert_eq!((brain.birth, brain.survival, brain.states), (1 << 2, 0, 3));
+    assert_eq!(brain.to_string(), "B2/S/C3");
+    assert_eq!("/2/3".parse(), Ok(brain));
+
+    let star_wars: Rule = "345/2/4".parse().unwrap();
+    assert_eq!(star_wars.survival, 1 << 3 | 1 << 4 | 1 << 5);
+    assert_eq!((star_wars.birth, star_wars.states), (1 << 2, 4));
+    assert_eq!(star_wars.to_string(), "B2/S345/C4");
+
+    // Two states is plain Life-like.
+    assert_eq!("B3/S23/C2".parse(), Ok(Rule::LIFE));
+    assert_eq!(Rule::LIFE.to_string(), "B3/S23");
+
+    for states in ["C1", "C257", "Cx", ""] {
+        assert_eq!(
+            format!("B2/S/{states}").parse::<Rule>(),
+            Err(RuleError::States(states.into()))
+        );
+    }
+}
+
+#[test]
+fn brians_brain_decays() {
+    let rule: Rule = "B2/S/C3".parse().unwrap();
+    let start = pattern(&["OO"], 5, 5);
+    // The pair dies into state 2 and is not counted, so each new pair is only born once.
+    let mut end = pattern(&["OO", "", "OO"], 5, 4);
+    end.set(5, 5, 2);
+    end.set(6, 5, 2);
+    check_rule(rule, &start, 1, &end);
+
+    let mut end = pattern(&[".OO", "", "O..O", "", ".OO"], 4, 3);
+    end.set(5, 4, 2);
+    end.set(6, 4, 2);
+    end.set(5, 6, 2);
+    end.set(6, 6, 2);
+    check_rule(rule, &start, 2, &end);
+}
+
+#[test]
+fn generations_on_both_backends() {
+    let grid = Grid::random(61, 37, 0.4, 5);
+    for rule in ["B2/S/C3", "345/2/4", "B3/S23/C8"] {
+        let rule: Rule = rule.parse().unwrap();
+        let mut cpu = CpuSimulator::new(grid.clone(), rule);
+        cpu.step_n(30);
+        let cells = cpu.read_cells();
+        assert!(cells.cell_arr.iter().any(|&state| state > 1));
+        assert!(cells.cell_arr.iter().all(|&state| state < rule.states));
+        if let Some(mut gpu) = gpu(&grid, rule) {
+            gpu.step_n(30);
+            assert!(gpu.read_cells().cell_arr == cells.cell_arr, "{rule}");
+        }
+    }
+}
+
+#[test]
+fn seeds_pair_is_born() {
+    let rule = "B2/S".parse().unwrap();
+    check_rule(
+        rule,
+        &pattern(&["OO"], 5, 5),
+        1,
+        &pattern(&["OO", "", "OO"], 5, 4),
+    );
+}
+
+#[test]
+fn highlife_six_neighbours_born() {
+    // Only the centre cell has six live neighbours, so it is the only difference from Life.
+    let start = pattern(&["OOO", "...", "OOO"], 5, 5);
+    let mut life = CpuSimulator::new(start.clone(), Rule::LIFE);
+    life.step();
+    let mut end = life.read_cells();
+    assert_eq!(end.get(6, 6), 0);
+    end.set(6, 6, 1);
+
+    check_rule("B36/S23".parse().unwrap(), &start, 1, &end);
+}
@@ -0,0 +1,68 @@
+use std::time::{Duration, Instant};
+
+use game_of_life::scheduler::{Rate, Scheduler};
+
+fn ticks(scheduler: &mut Scheduler, start: Instant, frame: Duration, frames: u32) -> u32 {
+    (1..=frames)
+        .map(|i| scheduler.tick(start + frame * i))
+        .sum()
+}
+
+#[test]
+fn fixed_rate_independent_of_frame_rate() {
+    for fps in [30, 60, 144] {
+        let mut scheduler = Scheduler::new(Rate::PerSecond(10.0));
+        let start = Instant::now();
+        scheduler.tick(start);
+        let frame = Duration::from_secs_f64(1.0 / fps as f64);
+        let stepped = ticks(&mut scheduler, start, frame, fps * 3);
+        assert!((29..=30).contains(&stepped), "{fps} fps stepped {stepped}");
+    }
+}
+
+#[test]
+fn fractional_rate() {
+    let mut scheduler = Scheduler::new(Rate::PerSecond(0.5));
+    let start = Instant::now();
+    scheduler.tick(start);
+    let frame = Duration::from_millis(100);
+    assert_eq!(ticks(&mut scheduler, start, frame, 19), 0);
+    assert_eq!(scheduler.tick(start + frame * 21), 1);
+}
+
+#[test]
+fn unlimited_steps_a_batch_per_frame() {
+    let mut scheduler = Scheduler::new(Rate::Unlimited);
+    scheduler.batch = 5;
+    assert_eq!(scheduler.tick(Instant::now()), 5);
+}
+
+#[test]
+fn backlog_is_capped() {
+    let mut scheduler = Scheduler::new(Rate::PerSecond(1000.0));
+    scheduler.max_catch_up = 100;
+    let start = Instant::now();
+    scheduler.tick(start);
+    assert_eq!(scheduler.tick(start + Duration::from_secs(10)), 100);
+    assert_eq!(scheduler.tick(start + Duration::from_millis(10_010)), 10);
+}
+
+#[test]
+fn paused_steps_nothing() {
+    let mut scheduler = Scheduler::new(Rate::PerSecond(10.0));
+    let start = Instant::now();
+    scheduler.tick(start);
+    scheduler.paused = true;
+    assert_eq!(scheduler.tick(start + Duration::from_secs(5)), 0);
+    scheduler.paused = false;
+    // Time spent paused is not made up for.
+    assert_eq!(scheduler.tick(start + Duration::from_millis(5100)), 1);
+}
+
+#[test]
+fn rate_steps() {
+    assert_eq!(Rate::PerSecond(1.0).faster(), Rate::PerSecond(2.0));
+    assert_eq!(Rate::PerSecond(512.0).faster(), Rate::Unlimited);
+    assert_eq!(Rate::Unlimited.slower(), Rate::PerSecond(512.0));
+    assert_eq!(Rate::PerSecond(0.125).slower(), Rate::PerSecond(0.125));
+}
@@ -0,0 +1,112 @@
+use game_of_life::{
+    camera::Camera,
+    cpu::CpuSimulator,
+    grid::Grid,
+    pattern::rle,
+    rule::Rule,
+    simulator::{Simulator, UnboundedSimulator},
+    sparse::{render::visible_chunks, SparseLife},
+    topology::Topology,
+};
+
+const GLIDER: &str = "x = 3, y = 3\nbo$2bo$3o!";
+
+const GOSPER_GUN: &str = "x = 36, y = 9
+24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b
+obo$10bo5bo7bo$11bo3bo$12b2o!";
+
+fn life(rle: &str, origin: (i64, i64)) -> SparseLife {
+    SparseLife::from_grid(&rle::parse(rle).unwrap().grid, Rule::LIFE, origin)
+}
+
+#[test]
+fn set_and_get_across_chunks() {
+    let mut life = SparseLife::new(Rule::LIFE);
+    for (x, y) in [(0, 0), (-1, -1), (63, 64), (-65, 1000)] {
+        assert!(!life.get(x, y));
+        life.set(x, y, true);
+        assert!(life.get(x, y));
+    }
+    assert_eq!(life.population(), 4);
+    assert_eq!(life.bounding_box(), Some(((-65, -1), (63, 1000))));
+
+    life.set(-65, 1000, false);
+    assert_eq!(life.chunks().count(), 3);
+    assert_eq!(life.bounding_box(), Some(((-1, -1), (63, 64))));
+}
+
+#[test]
+fn glider_never_wraps() {
+    // Starts straddling the four chunks around the origin, travels down and right.
+    let mut life = life(GLIDER, (-2, -2));
+    let start = life.to_grid().unwrap().0;
+    life.step_n(400);
+    assert_eq!(life.generation, 400);
+    assert_eq!(life.population(), 5);
+    let (grid, origin) = life.to_grid().unwrap();
+    assert_eq!(origin, (98, 98));
+    assert_eq!(grid.cell_arr, start.cell_arr);
+
+    let mut cells: Vec<_> = life.live_cells().collect();
+    cells.sort();
+    assert_eq!(
+        cells,
+        [(98, 100), (99, 98), (99, 100), (100, 99), (100, 100)]
+    );
+}
+
+#[test]
+fn gun_grows_forever() {
+    let mut life = life(GOSPER_GUN, (-18, -4));
+    life.step_n(30);
+    let population = life.population();
+    life.step_n(30 * 9);
+    // One glider per period.
+    assert_eq!(life.population(), population + 5 * 9);
+    let ((_, _), (max_x, max_y)) = life.bounding_box().unwrap();
+    assert!(max_x > 64 && max_y > 64);
+}
+
+#[test]
+fn matches_the_dense_plane() {
+    // Far enough from the edges of a dead-edged grid that both see the same universe.
+    let soup = Grid::random(100, 90, 0.35, 4);
+    let mut grid = Grid::empty(300, 300);
+    for y in 0..soup.height {
+        for x in 0..soup.width {
+            grid.set(100 + x, 100 + y, soup.get(x, y));
+        }
+    }
+    let mut cpu = CpuSimulator::new(grid, Rule::LIFE);
+    cpu.set_topology(Topology::PLANE);
+    let mut life = SparseLife::from_grid(&soup, Rule::LIFE, (-30, -70));
+
+    for _ in 0..40 {
+        cpu.step();
+        life.step();
+    }
+    let grid = cpu.read_cells();
+    let mut expected = vec![];
+    for y in 0..grid.height {
+        for x in 0..grid.width {
+            if grid.get(x, y) == 1 {
+                expected.push((x as i64 - 130, y as i64 - 170));
+            }
+        }
+    }
+    let mut cells: Vec<_> = life.live_cells().collect();
+    cells.sort_by_key(|&(x, y)| (y, x));
+    assert_eq!(cells, expected);
+}
+
+#[test]
+fn visible_chunk_range() {
+    let mut camera = Camera::new();
+    camera.zoom = 2.0;
+    // 256 x 128 pixels at 2 pixels per cell show cells -64..64 and -32..32.
+    assert_eq!(visible_chunks(&camera, 256, 128), ((-1, -1), (1, 0)));
+
+    camera.translation.x = 200.0;
+    let ((min_x, _), (max_x, _)) = visible_chunks(&camera, 256, 128);
+    assert_eq!((min_x, max_x), (2, 4));
+}
@@ -0,0 +1,257 @@
+use game_of_life::{
+    core::Core,
+    cpu::CpuSimulator,
+    gpu::GpuSimulator,
+    grid::Grid,
+    rule::{Rule, RuleError},
+    simulator::Simulator,
+    tiling::{Tiling, HEX_RADIUS, ROW_HEIGHT},
+    topology::{Edge, Topology},
+};
+use glam::Vec2;
+
+const TILINGS: [Tiling; 4] = [
+    Tiling::Square,
+    Tiling::Hexagonal,
+    Tiling::Triangular,
+    Tiling::TriangularVonNeumann,
+];
+
+/// Centre of the cell at `(x, y)` in world units from the bottom left of the grid.
+fn centre(tiling: Tiling, cell: (u32, u32), height: u32) -> Vec2 {
+    let offset = match tiling {
+        Tiling::Square => Vec2::new(0.5, 0.5),
+        Tiling::Hexagonal => Vec2::new(0.5, HEX_RADIUS),
+        _ if Tiling::points_up(cell) => Vec2::new(0.5, ROW_HEIGHT / 3.0),
+        _ => Vec2::new(0.5, ROW_HEIGHT * 2.0 / 3.0),
+    };
+    tiling.corner(cell, height) + offset
+}
+
+#[test]
+fn parse_and_display() {
+    for rule in ["B2/S34H", "B3/S2abL", "B2/S13LE", "B2/S/C3H", "B0c/S12L"] {
+        let parsed: Rule = rule.parse().unwrap();
+        assert_ne!(parsed.tiling, Tiling::Square, "{rule}");
+        assert!(!parsed.is_life_like());
+        assert_eq!(parsed.to_string(), rule);
+    }
+    let rule: Rule = "b2/s34h".parse().unwrap();
+    assert_eq!(rule.to_string(), "B2/S34H");
+    let rule: Rule = "B3/S2abL".parse().unwrap();
+    assert_eq!(rule.survival, 1 << 2 | 1 << 10 | 1 << 11);
+    assert_eq!("345/2/4H".parse::<Rule>().unwrap().states, 4);
+
+    assert_eq!("B7/S34H".parse::<Rule>(), Err(RuleError::Digit('7')));
+    assert_eq!("B4/S3LE".parse::<Rule>(), Err(RuleError::Digit('4')));
+    assert_eq!("B2a/S34H".parse::<Rule>(), Err(RuleError::Digit('a')));
+    assert_eq!("B3/S23".parse::<Rule>().unwrap().tiling, Tiling::Square);
+}
+
+#[test]
+fn neighbours_are_the_nearest_cells() {
+    let (width, height) = (10, 8);
+    for tiling in TILINGS {
+        // How far the centres of neighbours are at most, the next cells are further.
+        let reach = match tiling {
+            Tiling::Square => 1.5,
+            Tiling::Hexagonal => 1.01,
+            Tiling::Triangular => 1.2,
+            Tiling::TriangularVonNeumann => 0.6,
+        };
+        for y in 2..height - 2 {
+            for x in 2..width - 2 {
+                let mut expected: Vec<(i32, i32)> = (0..height)
+                    .flat_map(|ny| (0..width).map(move |nx| (nx, ny)))
+                    .filter(|&other| other != (x, y))
+                    .filter(|&other| {
+                        let distance =
+                            centre(tiling, other, height).distance(centre(tiling, (x, y), height));
+                        distance < reach
+                    })
+                    .map(|(nx, ny)| (nx as i32 - x as i32, ny as i32 - y as i32))
+                    .collect();
+                let mut neighbours: Vec<_> = tiling.neighbours((x, y)).collect();
+                expected.sort_unstable();
+                neighbours.sort_unstable();
+                assert_eq!(neighbours, expected, "{tiling:?} ({x}, {y})");
+                assert_eq!(neighbours.len() as u32, tiling.neighbour_count());
+            }
+        }
+    }
+}
+
+#[test]
+fn cells_are_picked_at_their_centres() {
+    let (width, height) = (7, 5);
+    for tiling in TILINGS {
+        let size = tiling.size((width, height));
+        for y in 0..height {
+            for x in 0..width {
+                let centre = centre(tiling, (x, y), height);
+                assert!(centre.x > 0.0 && centre.y > 0.0, "{tiling:?}");
+                assert!(centre.x < size.x && centre.y < size.y, "{tiling:?}");
+                assert_eq!(
+                    tiling.cell_at(centre, (width, height)),
+                    Some((x, y)),
+                    "{tiling:?}"
+                );
+            }
+        }
+        assert_eq!(tiling.cell_at(Vec2::new(-0.1, 0.1), (width, height)), None);
+        assert_eq!(tiling.cell_at(size + 0.1, (width, height)), None);
+    }
+}
+
+#[test]
+fn single_cell_gives_birth_to_its_neighbours() {
+    for (rule, cell, born) in [
+        (
+            "B1/SH",
+            (4, 4),
+            &[(3, 3), (4, 3), (3, 4), (5, 4), (4, 5), (5, 5)][..],
+        ),
+        // Pointing up, the base is towards the next row.
+        ("B1/SLE", (4, 4), &[(3, 4), (5, 4), (4, 5)]),
+        ("B1/SLE", (5, 4), &[(4, 4), (6, 4), (5, 3)]),
+    ] {
+        let rule: Rule = rule.parse().unwrap();
+        let mut start = Grid::empty(10, 10);
+        start.set(cell.0, cell.1, 1);
+        let mut end = Grid::empty(10, 10);
+        for &(x, y) in born {
+            end.set(x, y, 1);
+        }
+
+        let mut cpu = CpuSimulator::new(start.clone(), rule);
+        cpu.step();
+        assert!(cpu.read_cells().cell_arr == end.cell_arr, "cpu {rule}");
+
+        let Some(mut gpu) = pollster::block_on(GpuSimulator::headless(&start, rule)) else {
+            eprintln!("no adapter, skipping gpu check");
+            return;
+        };
+        gpu.step();
+        assert!(gpu.read_cells().cell_arr == end.cell_arr, "gpu {rule}");
+    }
+}
+
+#[test]
+fn gpu_matches_cpu() {
+    for rule in ["B2/S34H", "B2/S/C3H", "B3/S2abL", "B45/S34L", "B1/S12LE"] {
+        let rule: Rule = rule.parse().unwrap();
+        for topology in [Topology::TORUS, Topology::PLANE, Topology::KLEIN] {
+            let grid = Grid::random(30, 22, 0.4, 4);
+            let gpu = GpuSimulator::headless(&grid, rule);
+            let Some(mut gpu) = pollster::block_on(gpu) else {
+                eprintln!("no adapter, skipping gpu check");
+                return;
+            };
+            let mut cpu = CpuSimulator::new(grid, rule);
+            cpu.set_topology(topology);
+            gpu.set_topology(topology);
+            cpu.step_n(12);
+            gpu.step_n(12);
+            assert!(
+                cpu.read_cells().cell_arr == gpu.read_cells().cell_arr,
+                "{rule} {topology:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn render_and_pick_tilings() {
+    let (width, height) = (6, 4);
+    let mut grid = Grid::empty(width, height);
+    grid.set(2, 1, 1);
+    let Some(mut core) = pollster::block_on(Core::headless(96, 96, grid, Rule::default())) else {
+        eprintln!("no adapter, skipping headless check");
+        return;
+    };
+
+    for rule in ["B2/S34H", "B2/S34L", "B2/S34"] {
+        let rule: Rule = rule.parse().unwrap();
+        let tiling = rule.tiling;
+        core.set_rule(rule);
+        // Screen pixel of a world position, the grid centred in the window.
+        let zoom = core.camera.zoom;
+        let screen = |pos: Vec2| {
+            let pos = (pos - tiling.size((width, height)) / 2.0) * zoom;
+            (48.0 + pos.x as f64, 48.0 - pos.y as f64)
+        };
+
+        core.render();
+        let frame = core.read_frame().unwrap();
+        let (x, y) = screen(centre(tiling, (2, 1), height));
+        let live = *frame.get_pixel(x as u32, y as u32);
+        assert_eq!(core.cursor_to_cell(x, y), Some((2, 1)), "{tiling:?}");
+        for neighbour in [(1, 1), (3, 1), (2, 2)] {
+            let (x, y) = screen(centre(tiling, neighbour, height));
+            assert_ne!(*frame.get_pixel(x as u32, y as u32), live, "{tiling:?}");
+            assert_eq!(core.cursor_to_cell(x, y), Some(neighbour), "{tiling:?}");
+        }
+    }
+}
+
+/// Whether every cell is a neighbour of each of its neighbours, which a seam of cells that
+/// don't fit together breaks.
+fn symmetric(tiling: Tiling, topology: Topology, size: (u32, u32)) -> bool {
+    let neighbours = |cell: (u32, u32)| -> Vec<(u32, u32)> {
+        tiling
+            .neighbours(cell)
+            .filter_map(|offset| topology.neighbour(cell, offset, size))
+            .collect()
+    };
+    (0..size.1)
+        .flat_map(|y| (0..size.0).map(move |x| (x, y)))
+        .all(|cell| {
+            neighbours(cell)
+                .iter()
+                .all(|&n| neighbours(n).contains(&cell))
+        })
+}
+
+#[test]
+fn odd_sizes_on_wrapped_edges() {
+    let edges = [Edge::Dead, Edge::Wrap, Edge::Twist];
+    for tiling in TILINGS {
+        for topology in edges
+            .into_iter()
+            .flat_map(|x| edges.map(|y| Topology { x, y }))
+        {
+            for size in [(8, 6), (8, 7), (7, 6), (7, 7)] {
+                let checked = tiling.check_topology(topology, size);
+                let symmetric = symmetric(tiling, topology, size);
+                // Triangles with 3 neighbours only share edges along the row, so they look
+                // symmetric across some seams that still put two triangles the same way up.
+                if tiling == Tiling::TriangularVonNeumann {
+                    assert!(
+                        checked.is_err() || symmetric,
+                        "{tiling:?} {topology:?} {size:?}"
+                    );
+                } else {
+                    assert_eq!(
+                        checked.is_ok(),
+                        symmetric,
+                        "{tiling:?} {topology:?} {size:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    assert_eq!(
+        Tiling::Triangular.check_topology(Topology::TORUS, (31, 20)),
+        Err("triangles need an even width to wrap, the grid is 31 x 20".to_string())
+    );
+    assert!(Tiling::Hexagonal
+        .check_topology(Topology::TORUS, (31, 21))
+        .is_ok());
+    assert!(Tiling::Hexagonal
+        .check_topology(Topology::KLEIN, (30, 20))
+        .is_err());
+    assert!(Tiling::Square
+        .check_topology(Topology::CROSS_SURFACE, (31, 21))
+        .is_ok());
+}
@@ -0,0 +1,161 @@
+use game_of_life::{
+    cpu::CpuSimulator,
+    grid::Grid,
+    pattern::{rle, Format},
+    rule::Rule,
+    simulator::Simulator,
+    topology::{BoundedGrid, Edge, Topology},
+};
+
+#[test]
+fn parse_bounded_grids() {
+    let parse = |s: &str| s.parse::<BoundedGrid>().unwrap();
+    assert_eq!(parse("T100,80").topology, Topology::TORUS);
+    assert_eq!((parse("T100,80").width, parse("T100,80").height), (100, 80));
+    assert_eq!(parse("P10,10").topology, Topology::PLANE);
+    assert_eq!(parse("K100*,100").topology, Topology::KLEIN);
+    assert_eq!(
+        parse("K100,100*").topology,
+        Topology {
+            x: Edge::Twist,
+            y: Edge::Wrap
+        }
+    );
+    assert_eq!(parse("C50,50").topology, Topology::CROSS_SURFACE);
+    assert_eq!(parse("T100,0").topology, Topology::CYLINDER);
+
+    for s in [
+        "T100,80",
+        "P10,10",
+        "K100*,100",
+        "K100,100*",
+        "C50,50",
+        "T100,0",
+    ] {
+        assert_eq!(parse(s).to_string(), s);
+    }
+    for s in ["T100", "X10,10", "T10+2,10", "K0,10*", "K10*,10*", "S10,10"] {
+        assert!(s.parse::<BoundedGrid>().is_err(), "{s}");
+    }
+}
+
+#[test]
+fn bounded_grids_round_trip() {
+    use std::fmt::Write;
+
+    let edges = [Edge::Dead, Edge::Wrap, Edge::Twist];
+    for x in edges {
+        for y in edges {
+            let plane = (x, y) == (Edge::Dead, Edge::Dead);
+            // Golly writes a dead axis next to a wrapped one as size 0.
+            let size = |edge, size| {
+                if edge == Edge::Dead && !plane {
+                    0
+                } else {
+                    size
+                }
+            };
+            let bounds = BoundedGrid {
+                topology: Topology { x, y },
+                width: size(x, 30),
+                height: size(y, 20),
+            };
+            let mut s = String::new();
+            match write!(s, "{bounds}") {
+                Ok(()) => assert_eq!(s.parse::<BoundedGrid>(), Ok(bounds), "{s}"),
+                Err(_) => assert!(
+                    matches!(
+                        (x, y),
+                        (Edge::Twist, Edge::Dead) | (Edge::Dead, Edge::Twist)
+                    ),
+                    "{bounds:?}"
+                ),
+            }
+        }
+    }
+    assert_eq!(
+        BoundedGrid {
+            topology: Topology {
+                x: Edge::Dead,
+                y: Edge::Wrap
+            },
+            width: 0,
+            height: 20
+        }
+        .to_string(),
+        "T0,20"
+    );
+
+    // Sizes the form can't carry.
+    for (topology, width, height) in [
+        (Topology::CYLINDER, 30, 20),
+        (Topology::TORUS, 0, 20),
+        (Topology::KLEIN, 30, 0),
+    ] {
+        let bounds = BoundedGrid {
+            topology,
+            width,
+            height,
+        };
+        assert!(write!(String::new(), "{bounds}").is_err(), "{bounds:?}");
+        assert_eq!(Rule::LIFE.to_bounded_string(Some(bounds)), "B3/S23");
+    }
+}
+
+#[test]
+fn rule_with_bounds() {
+    let (rule, bounds) = Rule::parse_bounded("B3/S23:K20*,10").unwrap();
+    assert_eq!(rule, Rule::LIFE);
+    assert_eq!(bounds.unwrap().topology, Topology::KLEIN);
+    assert_eq!(Rule::parse_bounded("B3/S23").unwrap(), (Rule::LIFE, None));
+    assert!(Rule::parse_bounded("B3/S23:Q1,1").is_err());
+
+    let pattern = rle::parse("x = 3, y = 1, rule = B3/S23:T30,20\n3o!").unwrap();
+    assert_eq!(pattern.bounds, Some("T30,20".parse().unwrap()));
+    let written = pattern.write(Format::Rle);
+    assert!(written.contains("rule = B3/S23:T30,20"), "{written}");
+}
+
+#[test]
+fn twisted_neighbours() {
+    let size = (5, 4);
+    assert_eq!(Topology::PLANE.neighbour((0, 1), (-1, 0), size), None);
+    assert_eq!(
+        Topology::TORUS.neighbour((0, 1), (-1, 0), size),
+        Some((4, 1))
+    );
+    // Crossing the top of a Klein bottle comes back in at the bottom, mirrored.
+    assert_eq!(
+        Topology::KLEIN.neighbour((1, 0), (0, -1), size),
+        Some((3, 3))
+    );
+    assert_eq!(
+        Topology::KLEIN.neighbour((0, 1), (-1, 0), size),
+        Some((4, 1))
+    );
+    assert_eq!(
+        Topology::CROSS_SURFACE.neighbour((0, 1), (-1, 0), size),
+        Some((4, 2))
+    );
+    assert_eq!(Topology::CYLINDER.neighbour((1, 3), (0, 1), size), None);
+}
+
+#[test]
+fn plane_edges_are_dead() {
+    // A blinker against the edge of a plane loses its outer cells, on a torus it oscillates.
+    let mut grid = Grid::empty(5, 5);
+    for y in 1..4 {
+        grid.set(0, y, 1);
+    }
+
+    let mut torus = CpuSimulator::new(grid.clone(), Rule::LIFE);
+    torus.step_n(2);
+    assert_eq!(torus.read_cells().cell_arr, grid.cell_arr);
+
+    let mut plane = CpuSimulator::new(grid, Rule::LIFE);
+    plane.set_topology(Topology::PLANE);
+    plane.step();
+    let cells = plane.read_cells();
+    assert_eq!(cells.cell_arr.iter().sum::<u32>(), 2);
+    assert_eq!((cells.get(0, 2), cells.get(1, 2)), (1, 1));
+}